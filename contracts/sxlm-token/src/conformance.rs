@@ -0,0 +1,353 @@
+#![cfg(test)]
+
+// SEP-41 conformance suite
+//
+// Every scenario drives a token purely through the SEP-41 interface
+// (`soroban_sdk::token::TokenClient`) and records what an integrator can
+// observe: call results, balances, allowances, emitted events and the auth
+// tree each call required. The same scenarios run against `SxlmToken` and the
+// Stellar Asset Contract, which serves as the reference implementation, and
+// any difference between the two is reported as a divergence.
+//
+// Error codes are contract specific, so results are recorded as ok/err only.
+// Topics and data are rendered with addresses replaced by role labels so the
+// two runs can be compared line by line.
+
+extern crate std;
+
+use crate::{SxlmToken, SxlmTokenClient};
+use soroban_sdk::{
+    testutils::{Address as _, AuthorizedFunction, Events, Ledger},
+    token::{StellarAssetClient, TokenClient},
+    Address, Env, Map, Symbol, TryFromVal, Val, Vec,
+};
+use std::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec as StdVec,
+};
+
+/// Divergences from the Stellar Asset Contract that are deliberate, with the
+/// reason, in scenario order. Anything else the suite finds fails the test.
+const KNOWN_DIVERGENCES: &[(&str, &str)] = &[
+    // sXLM rejects zero amounts; the SAC accepts them as no-ops
+    ("zero_amounts", "zero-amount transfer, transfer_from and burn_from are rejected"),
+    // The vault burns on redemption, so `burn` is reserved for the minter.
+    // Holders burn their own sXLM with `burn_self`.
+    ("burn", "burn requires the minter's auth rather than the holder's"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Sxlm,
+    Sac,
+}
+
+/// A token under test plus the roles a scenario refers to by label
+struct Subject<'a> {
+    env: Env,
+    kind: Kind,
+    token: TokenClient<'a>,
+    alice: Address,
+    bob: Address,
+    spender: Address,
+}
+
+impl Subject<'_> {
+    fn new(env: &Env, kind: Kind) -> Self {
+        let admin = Address::generate(env);
+        let address = match kind {
+            Kind::Sxlm => {
+                let id = env.register(SxlmToken, ());
+                SxlmTokenClient::new(env, &id).initialize(
+                    &admin,
+                    &admin,
+                    &7,
+                    &soroban_sdk::String::from_str(env, "Staked XLM"),
+                    &soroban_sdk::String::from_str(env, "sXLM"),
+                );
+                id
+            }
+            Kind::Sac => env.register_stellar_asset_contract_v2(admin).address(),
+        };
+        Subject {
+            env: env.clone(),
+            kind,
+            token: TokenClient::new(env, &address),
+            alice: Address::generate(env),
+            bob: Address::generate(env),
+            spender: Address::generate(env),
+        }
+    }
+
+    /// Credit `to` with `amount` outside the SEP-41 interface
+    fn fund(&self, to: &Address, amount: i128) {
+        match self.kind {
+            Kind::Sxlm => {
+                let sxlm = SxlmTokenClient::new(&self.env, &self.token.address);
+                sxlm.mint(&sxlm.minter(), to, &amount)
+            }
+            Kind::Sac => StellarAssetClient::new(&self.env, &self.token.address).mint(to, &amount),
+        }
+    }
+
+    fn label(&self, address: &Address) -> String {
+        let roles = [
+            (&self.alice, "alice"),
+            (&self.bob, "bob"),
+            (&self.spender, "spender"),
+            (&self.token.address, "token"),
+        ];
+        match roles.iter().find(|(role, _)| *role == address) {
+            Some((_, label)) => String::from(*label),
+            None => String::from("admin"),
+        }
+    }
+
+    /// Render a value with addresses replaced by their role labels
+    fn describe(&self, val: &Val) -> String {
+        let env = &self.env;
+        if let Ok(address) = Address::try_from_val(env, val) {
+            return self.label(&address);
+        }
+        if let Ok(symbol) = Symbol::try_from_val(env, val) {
+            return symbol.to_string();
+        }
+        if let Ok(n) = u32::try_from_val(env, val) {
+            return format!("{}u32", n);
+        }
+        if let Ok(n) = i128::try_from_val(env, val) {
+            return format!("{}", n);
+        }
+        if let Ok(s) = soroban_sdk::String::try_from_val(env, val) {
+            return format!("{:?}", s.to_string());
+        }
+        if let Ok(items) = Vec::<Val>::try_from_val(env, val) {
+            let items: StdVec<String> = items.iter().map(|item| self.describe(&item)).collect();
+            return format!("[{}]", items.join(", "));
+        }
+        if let Ok(map) = Map::<Val, Val>::try_from_val(env, val) {
+            let entries: StdVec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{}: {}", self.describe(&k), self.describe(&v)))
+                .collect();
+            return format!("{{{}}}", entries.join(", "));
+        }
+        String::from("?")
+    }
+
+    /// Events the token emitted during the last invocation. The SAC appends
+    /// the asset name as a final string topic, which SEP-41 allows, so
+    /// trailing string topics are dropped before comparing.
+    fn events(&self) -> StdVec<String> {
+        let mut lines = StdVec::new();
+        for (contract, topics, data) in self.env.events().all().iter() {
+            if contract != self.token.address {
+                continue;
+            }
+            let mut topics: StdVec<Val> = topics.iter().collect();
+            if let Some(last) = topics.last() {
+                if soroban_sdk::String::try_from_val(&self.env, last).is_ok() {
+                    topics.pop();
+                }
+            }
+            let topics: StdVec<String> = topics.iter().map(|t| self.describe(t)).collect();
+            lines.push(format!("event ({}) {}", topics.join(", "), self.describe(&data)));
+        }
+        lines
+    }
+
+    /// Auth tree recorded for the last invocation
+    fn auths(&self) -> StdVec<String> {
+        self.env
+            .auths()
+            .iter()
+            .map(|(signer, invocation)| match &invocation.function {
+                AuthorizedFunction::Contract((_, function, args)) => {
+                    let args: StdVec<String> = args.iter().map(|a| self.describe(&a)).collect();
+                    format!(
+                        "auth {} {}({}) subs={}",
+                        self.label(signer),
+                        function.to_string(),
+                        args.join(", "),
+                        invocation.sub_invocations.len()
+                    )
+                }
+                _ => String::from("auth ?"),
+            })
+            .collect()
+    }
+
+    /// Record the outcome, events and auths of a call made through `call`,
+    /// which returns whether the token accepted it
+    fn observe(&self, log: &mut StdVec<String>, name: &str, ok: bool) {
+        log.push(format!("{} -> {}", name, if ok { "ok" } else { "err" }));
+        if ok {
+            log.extend(self.events());
+            log.extend(self.auths());
+        }
+    }
+
+    fn balances(&self, log: &mut StdVec<String>) {
+        log.push(format!(
+            "balances alice={} bob={} spender={}",
+            self.token.balance(&self.alice),
+            self.token.balance(&self.bob),
+            self.token.balance(&self.spender)
+        ));
+    }
+}
+
+// ============ Scenarios ============
+
+fn metadata(s: &Subject) -> StdVec<String> {
+    vec![format!("decimals {}", s.token.decimals())]
+}
+
+fn transfer(s: &Subject) -> StdVec<String> {
+    let mut log = StdVec::new();
+    s.fund(&s.alice, 1_000);
+    let ok = s.token.try_transfer(&s.alice, &s.bob, &300).is_ok();
+    s.observe(&mut log, "transfer 300", ok);
+    let ok = s.token.try_transfer(&s.alice, &s.bob, &701).is_ok();
+    s.observe(&mut log, "transfer above balance", ok);
+    let ok = s.token.try_transfer(&s.alice, &s.bob, &-1).is_ok();
+    s.observe(&mut log, "transfer negative", ok);
+    s.balances(&mut log);
+    log
+}
+
+fn self_transfer(s: &Subject) -> StdVec<String> {
+    let mut log = StdVec::new();
+    s.fund(&s.alice, 1_000);
+    let ok = s.token.try_transfer(&s.alice, &s.alice, &400).is_ok();
+    s.observe(&mut log, "transfer to self", ok);
+    s.balances(&mut log);
+    log
+}
+
+fn zero_amounts(s: &Subject) -> StdVec<String> {
+    let mut log = StdVec::new();
+    s.fund(&s.alice, 1_000);
+    let ok = s.token.try_transfer(&s.alice, &s.bob, &0).is_ok();
+    s.observe(&mut log, "transfer 0", ok);
+    let ok = s.token.try_approve(&s.alice, &s.spender, &0, &0).is_ok();
+    s.observe(&mut log, "approve 0", ok);
+    let ok = s.token.try_transfer_from(&s.spender, &s.alice, &s.bob, &0).is_ok();
+    s.observe(&mut log, "transfer_from 0", ok);
+    let ok = s.token.try_burn_from(&s.spender, &s.alice, &0).is_ok();
+    s.observe(&mut log, "burn_from 0", ok);
+    s.balances(&mut log);
+    log
+}
+
+fn allowance(s: &Subject) -> StdVec<String> {
+    let mut log = StdVec::new();
+    s.fund(&s.alice, 1_000);
+    let expiration = s.env.ledger().sequence() + 100;
+
+    let ok = s.token.try_approve(&s.alice, &s.spender, &500, &expiration).is_ok();
+    s.observe(&mut log, "approve 500", ok);
+    log.push(format!("allowance {}", s.token.allowance(&s.alice, &s.spender)));
+
+    let ok = s.token.try_transfer_from(&s.spender, &s.alice, &s.bob, &200).is_ok();
+    s.observe(&mut log, "transfer_from 200", ok);
+    log.push(format!("allowance {}", s.token.allowance(&s.alice, &s.spender)));
+
+    let ok = s.token.try_transfer_from(&s.spender, &s.alice, &s.bob, &301).is_ok();
+    s.observe(&mut log, "transfer_from above allowance", ok);
+
+    let ok = s.token.try_burn_from(&s.spender, &s.alice, &100).is_ok();
+    s.observe(&mut log, "burn_from 100", ok);
+    log.push(format!("allowance {}", s.token.allowance(&s.alice, &s.spender)));
+
+    // A new approval replaces the old one rather than adding to it
+    let ok = s.token.try_approve(&s.alice, &s.spender, &50, &expiration).is_ok();
+    s.observe(&mut log, "approve 50", ok);
+    log.push(format!("allowance {}", s.token.allowance(&s.alice, &s.spender)));
+    s.balances(&mut log);
+    log
+}
+
+fn allowance_expiry(s: &Subject) -> StdVec<String> {
+    let mut log = StdVec::new();
+    s.fund(&s.alice, 1_000);
+    let now = s.env.ledger().sequence();
+
+    let ok = s.token.try_approve(&s.alice, &s.spender, &500, &(now - 1)).is_ok();
+    s.observe(&mut log, "approve already expired", ok);
+
+    let ok = s.token.try_approve(&s.alice, &s.spender, &500, &(now + 10)).is_ok();
+    s.observe(&mut log, "approve until now+10", ok);
+
+    s.env.ledger().with_mut(|l| l.sequence_number = now + 10);
+    log.push(format!("allowance at expiry {}", s.token.allowance(&s.alice, &s.spender)));
+
+    s.env.ledger().with_mut(|l| l.sequence_number = now + 11);
+    log.push(format!("allowance after expiry {}", s.token.allowance(&s.alice, &s.spender)));
+    let ok = s.token.try_transfer_from(&s.spender, &s.alice, &s.bob, &1).is_ok();
+    s.observe(&mut log, "transfer_from after expiry", ok);
+
+    s.env.ledger().with_mut(|l| l.sequence_number = now);
+    log
+}
+
+fn burn(s: &Subject) -> StdVec<String> {
+    let mut log = StdVec::new();
+    s.fund(&s.alice, 1_000);
+    let ok = s.token.try_burn(&s.alice, &100).is_ok();
+    s.observe(&mut log, "burn 100", ok);
+    let ok = s.token.try_burn(&s.alice, &901).is_ok();
+    s.observe(&mut log, "burn above balance", ok);
+    s.balances(&mut log);
+    log
+}
+
+type Scenario = fn(&Subject) -> StdVec<String>;
+
+const SCENARIOS: &[(&str, Scenario)] = &[
+    ("metadata", metadata),
+    ("transfer", transfer),
+    ("self_transfer", self_transfer),
+    ("zero_amounts", zero_amounts),
+    ("allowance", allowance),
+    ("allowance_expiry", allowance_expiry),
+    ("burn", burn),
+];
+
+/// Run every scenario against both tokens and return the names of those
+/// whose observations differ, printing each difference
+fn divergences(env: &Env) -> StdVec<&'static str> {
+    let mut diverged = StdVec::new();
+    for (name, scenario) in SCENARIOS {
+        let sac = scenario(&Subject::new(env, Kind::Sac));
+        let sxlm = scenario(&Subject::new(env, Kind::Sxlm));
+        if sac != sxlm {
+            std::println!("SEP-41 divergence in `{}`", name);
+            for i in 0..sac.len().max(sxlm.len()) {
+                let expected = sac.get(i).map(String::as_str).unwrap_or("<none>");
+                let actual = sxlm.get(i).map(String::as_str).unwrap_or("<none>");
+                if expected != actual {
+                    std::println!("  sac:  {}\n  sxlm: {}", expected, actual);
+                }
+            }
+            diverged.push(*name);
+        }
+    }
+    diverged
+}
+
+#[test]
+fn test_sep41_conformance() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|l| l.sequence_number = 1_000);
+
+    let known: StdVec<&str> = KNOWN_DIVERGENCES.iter().map(|(name, _)| *name).collect();
+    assert_eq!(
+        divergences(&env),
+        known,
+        "sXLM diverges from the Stellar Asset Contract; see the output above"
+    );
+}
@@ -1,9 +1,20 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, Address, Env, String,
+    contract, contracterror, contractimpl, contracttype, Address, Env, IntoVal, Map, String,
+    Symbol, Vec,
 };
-use soroban_token_sdk::TokenUtils;
+use soroban_token_sdk::{metadata::TokenMetadata, TokenUtils};
+
+// Storage layout version this code expects. Bump it together with a new
+// step in `migrate_step` whenever an upgrade changes how storage is laid out.
+const SCHEMA_VERSION: u32 = 6;
+
+// Largest `decimals` a token can be initialized with
+const MAX_DECIMALS: u32 = 18;
+
+// Mint cap meaning "no cap", given to the primary minter
+const UNCAPPED: i128 = i128::MAX;
 
 // Storage TTL constants
 const DAY_IN_LEDGERS: u32 = 17280; // ~24 hours
@@ -16,13 +27,88 @@ const BALANCE_LIFETIME_THRESHOLD: u32 = 7 * DAY_IN_LEDGERS; // 7 days
 #[contracttype]
 pub enum DataKey {
     Admin,
-    Minter,
+    Minter,  // Primary minter (the vault): uncapped, replaced through `propose_minter`
+    Minters, // Map of every minter, primary included, to its allowance
     Balance(Address),
-    Allowance(Address, Address),
+    Allowance(Address, Address), // Temporary entry living until the allowance expires
     TotalSupply,
-    Name,
-    Symbol,
-    Decimals,
+    Name,     // Legacy, moved into `TokenMetadata` at schema v3
+    Symbol,   // Legacy, moved into `TokenMetadata` at schema v3
+    Decimals, // Legacy, moved into `TokenMetadata` at schema v3
+    PendingAdmin,
+    PendingMinter,
+    SchemaVersion,
+    TransfersPaused, // Legacy, moved into `TransferPauses` at schema v5
+    TransferHook,    // Legacy, moved into `TransferHooks` at schema v5
+    Compliance,
+    Frozen(Address), // Persistent flag, present only while the address is frozen
+    TransferPauses,  // Admin and minters currently holding a transfer pause
+    TransferHooks,   // Map of minter to the contract notified of transfers
+    Issued(Address), // Map of minter to the holder's sXLM it issued, primary excluded
+}
+
+/// Stable error codes returned by the token entrypoints.
+/// Codes are part of the public interface: never renumber, only append.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum TokenError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    InvalidAmount = 3,
+    NegativeAmount = 4,
+    InsufficientBalance = 5,
+    InsufficientAllowance = 6,
+    Overflow = 7,
+    NoPendingTransfer = 8,
+    TransferExpired = 9,
+    SchemaOutdated = 10,
+    InvalidSchemaVersion = 11,
+    TransfersPaused = 12,
+    InvalidExpiration = 13,
+    InvalidMetadata = 14,
+    AccountFrozen = 15,
+    Unauthorized = 16,
+    NotMinter = 17,
+    MinterExists = 18,
+    MinterCapExceeded = 19,
+    PrimaryMinter = 20,
+    ExceedsOutstanding = 21,
+    ExceedsIssued = 22, // The holder has less than `amount` of the minter's sXLM
+}
+
+/// An allowance and the last ledger it can be spent in
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+/// How much a minter may have outstanding (minted and not yet burned by it)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct MinterAllowance {
+    pub cap: i128, // `i128::MAX` for no cap
+    pub outstanding: i128,
+}
+
+/// A minter with its allowance and remaining headroom, as listed by `minters`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct MinterInfo {
+    pub minter: Address,
+    pub cap: i128,
+    pub outstanding: i128,
+    pub headroom: i128,
+}
+
+/// A proposed admin or minter change that takes effect once `address` accepts it
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PendingTransfer {
+    pub address: Address,
+    pub expires_ledger: Option<u32>, // Last ledger the proposal can be accepted
 }
 
 fn extend_instance_ttl(env: &Env) {
@@ -56,6 +142,277 @@ fn set_balance(env: &Env, addr: &Address, amount: i128) {
     extend_balance_ttl(env, addr);
 }
 
+fn get_admin(env: &Env) -> Result<Address, TokenError> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .ok_or(TokenError::NotInitialized)
+}
+
+fn get_minter(env: &Env) -> Result<Address, TokenError> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Minter)
+        .ok_or(TokenError::NotInitialized)
+}
+
+fn get_minters(env: &Env) -> Map<Address, MinterAllowance> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Minters)
+        .unwrap_or(Map::new(env))
+}
+
+fn set_minters(env: &Env, minters: &Map<Address, MinterAllowance>) {
+    env.storage().instance().set(&DataKey::Minters, minters);
+}
+
+/// Authorize `minter` and return its allowance
+fn require_minter(env: &Env, minter: &Address) -> Result<MinterAllowance, TokenError> {
+    minter.require_auth();
+    get_minters(env).get(minter.clone()).ok_or(TokenError::NotMinter)
+}
+
+fn headroom(allowance: &MinterAllowance) -> i128 {
+    (allowance.cap - allowance.outstanding).max(0)
+}
+
+/// Stored schema version; deployments from before versioning read as 0
+fn get_schema_version(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::SchemaVersion)
+        .unwrap_or(0)
+}
+
+/// Refuse to change state while storage is behind the code (run `migrate`)
+fn require_current_schema(env: &Env) -> Result<(), TokenError> {
+    get_admin(env)?;
+    if get_schema_version(env) < SCHEMA_VERSION {
+        return Err(TokenError::SchemaOutdated);
+    }
+    Ok(())
+}
+
+/// Transform storage from `version - 1` to `version`
+fn migrate_step(env: &Env, version: u32) -> Result<(), TokenError> {
+    match version {
+        // Pre-versioning deployments already use the v1 layout
+        1 => Ok(()),
+        // Allowances moved to temporary storage with an expiration. Legacy
+        // persistent approvals had none and cannot be enumerated here, so the
+        // upgrade revokes them: they read as zero and `approve` deletes the
+        // stale entry for its pair. Holders must re-approve with an expiration.
+        2 => Ok(()),
+        // Name and symbol moved from ad-hoc keys into the standard
+        // `TokenMetadata` entry. Decimals were always reported as 7.
+        3 => {
+            let storage = env.storage().instance();
+            let name: String = storage.get(&DataKey::Name).ok_or(TokenError::NotInitialized)?;
+            let symbol: String = storage.get(&DataKey::Symbol).ok_or(TokenError::NotInitialized)?;
+            write_metadata(env, 7, name, symbol);
+            storage.remove(&DataKey::Name);
+            storage.remove(&DataKey::Symbol);
+            storage.remove(&DataKey::Decimals);
+            Ok(())
+        }
+        // The single minter became the primary entry of the minter set. It
+        // keeps no cap and is charged with the whole existing supply.
+        4 => {
+            let minter = get_minter(env)?;
+            let allowance = MinterAllowance {
+                cap: UNCAPPED,
+                outstanding: get_total_supply(env),
+            };
+            let mut minters = Map::new(env);
+            minters.set(minter, allowance);
+            set_minters(env, &minters);
+            Ok(())
+        }
+        // The transfer pause and hook belonged to the single minter. Both
+        // became per caller, so the existing ones are handed to the primary.
+        5 => {
+            let storage = env.storage().instance();
+            let minter = get_minter(env)?;
+            let mut pauses = Vec::new(env);
+            if storage.get(&DataKey::TransfersPaused).unwrap_or(false) {
+                pauses.push_back(minter.clone());
+            }
+            set_transfer_pauses(env, &pauses);
+            let mut hooks = Map::new(env);
+            if let Some(hook) = storage.get::<_, Address>(&DataKey::TransferHook) {
+                hooks.set(minter, hook);
+            }
+            set_transfer_hooks(env, &hooks);
+            storage.remove(&DataKey::TransfersPaused);
+            storage.remove(&DataKey::TransferHook);
+            Ok(())
+        }
+        // Holders' sXLM became attributed to the minter that issued it, with
+        // the primary owning whatever is not recorded. Earlier mints by other
+        // minters were not recorded, so their supply is charged to the primary.
+        6 => {
+            let primary = get_minter(env)?;
+            let mut minters = get_minters(env);
+            let mut moved: i128 = 0;
+            for (minter, mut allowance) in minters.clone().iter() {
+                if minter != primary && allowance.outstanding > 0 {
+                    moved = moved
+                        .checked_add(allowance.outstanding)
+                        .ok_or(TokenError::Overflow)?;
+                    allowance.outstanding = 0;
+                    minters.set(minter, allowance);
+                }
+            }
+            let mut allowance = minters.get(primary.clone()).ok_or(TokenError::NotMinter)?;
+            allowance.outstanding = allowance
+                .outstanding
+                .checked_add(moved)
+                .ok_or(TokenError::Overflow)?;
+            minters.set(primary, allowance);
+            set_minters(env, &minters);
+            Ok(())
+        }
+        _ => Err(TokenError::InvalidSchemaVersion),
+    }
+}
+
+fn read_metadata(env: &Env) -> TokenMetadata {
+    TokenUtils::new(env).metadata().get_metadata()
+}
+
+fn write_metadata(env: &Env, decimal: u32, name: String, symbol: String) {
+    let metadata = TokenMetadata {
+        decimal,
+        name,
+        symbol,
+    };
+    TokenUtils::new(env).metadata().set_metadata(&metadata);
+}
+
+fn validate_name_and_symbol(name: &String, symbol: &String) -> Result<(), TokenError> {
+    if name.is_empty() || symbol.is_empty() {
+        return Err(TokenError::InvalidMetadata);
+    }
+    Ok(())
+}
+
+/// Store a proposal under `key`, replacing any pending one
+fn propose_transfer(env: &Env, key: &DataKey, address: &Address, expires_ledger: Option<u32>) {
+    let pending = PendingTransfer {
+        address: address.clone(),
+        expires_ledger,
+    };
+    env.storage().instance().set(key, &pending);
+}
+
+/// Authorize the proposed address, check expiry and clear the proposal
+fn take_pending_transfer(env: &Env, key: &DataKey) -> Result<Address, TokenError> {
+    let pending: PendingTransfer = env
+        .storage()
+        .instance()
+        .get(key)
+        .ok_or(TokenError::NoPendingTransfer)?;
+    pending.address.require_auth();
+
+    if let Some(expires_ledger) = pending.expires_ledger {
+        if env.ledger().sequence() > expires_ledger {
+            return Err(TokenError::TransferExpired);
+        }
+    }
+
+    env.storage().instance().remove(key);
+    Ok(pending.address)
+}
+
+fn cancel_pending_transfer(env: &Env, key: &DataKey) -> Result<(), TokenError> {
+    if !env.storage().instance().has(key) {
+        return Err(TokenError::NoPendingTransfer);
+    }
+    env.storage().instance().remove(key);
+    Ok(())
+}
+
+fn get_transfer_pauses(env: &Env) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&DataKey::TransferPauses)
+        .unwrap_or(Vec::new(env))
+}
+
+fn set_transfer_pauses(env: &Env, pauses: &Vec<Address>) {
+    env.storage().instance().set(&DataKey::TransferPauses, pauses);
+}
+
+/// Transfers stay paused while the admin or any minter holds a pause
+fn transfers_paused(env: &Env) -> bool {
+    !get_transfer_pauses(env).is_empty()
+}
+
+/// Add or drop `holder`'s pause
+fn hold_transfer_pause(env: &Env, holder: &Address, paused: bool) {
+    let mut pauses = get_transfer_pauses(env);
+    match (pauses.first_index_of(holder), paused) {
+        (None, true) => pauses.push_back(holder.clone()),
+        (Some(index), false) => {
+            pauses.remove(index);
+        }
+        _ => return,
+    }
+    set_transfer_pauses(env, &pauses);
+}
+
+fn get_transfer_hooks(env: &Env) -> Map<Address, Address> {
+    env.storage()
+        .instance()
+        .get(&DataKey::TransferHooks)
+        .unwrap_or(Map::new(env))
+}
+
+fn set_transfer_hooks(env: &Env, hooks: &Map<Address, Address>) {
+    env.storage().instance().set(&DataKey::TransferHooks, hooks);
+}
+
+fn is_frozen(env: &Env, id: &Address) -> bool {
+    let key = DataKey::Frozen(id.clone());
+    let frozen = env.storage().persistent().has(&key);
+    if frozen {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    }
+    frozen
+}
+
+fn require_not_frozen(env: &Env, id: &Address) -> Result<(), TokenError> {
+    if is_frozen(env, id) {
+        return Err(TokenError::AccountFrozen);
+    }
+    Ok(())
+}
+
+/// Authorize `caller` as the admin or the compliance officer
+fn require_compliance(env: &Env, caller: &Address) -> Result<(), TokenError> {
+    caller.require_auth();
+    let compliance: Option<Address> = env.storage().instance().get(&DataKey::Compliance);
+    if *caller != get_admin(env)? && compliance.as_ref() != Some(caller) {
+        return Err(TokenError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Tell every minter's transfer hook (its vault, for cost basis tracking)
+/// that `amount` moved from `from` to `to`. Called after balances are updated.
+fn notify_transfer(env: &Env, from: &Address, to: &Address, amount: i128) {
+    for hook in get_transfer_hooks(env).values() {
+        env.invoke_contract::<()>(
+            &hook,
+            &Symbol::new(env, "on_transfer"),
+            (from.clone(), to.clone(), amount).into_val(env),
+        );
+    }
+}
+
 fn get_total_supply(env: &Env) -> i128 {
     env.storage()
         .instance()
@@ -67,21 +424,257 @@ fn set_total_supply(env: &Env, amount: i128) {
     env.storage().instance().set(&DataKey::TotalSupply, &amount);
 }
 
+/// The allowance `spender` may spend from `from`, zero once expired
+fn read_allowance(env: &Env, from: &Address, spender: &Address) -> AllowanceValue {
+    let key = DataKey::Allowance(from.clone(), spender.clone());
+    match env.storage().temporary().get::<_, AllowanceValue>(&key) {
+        Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+        _ => AllowanceValue {
+            amount: 0,
+            expiration_ledger: 0,
+        },
+    }
+}
+
 fn get_allowance(env: &Env, from: &Address, spender: &Address) -> i128 {
+    read_allowance(env, from, spender).amount
+}
+
+/// Store an allowance whose entry lives exactly until `expiration_ledger`
+fn set_allowance(
+    env: &Env,
+    from: &Address,
+    spender: &Address,
+    amount: i128,
+    expiration_ledger: u32,
+) -> Result<(), TokenError> {
     let key = DataKey::Allowance(from.clone(), spender.clone());
-    env.storage().persistent().get(&key).unwrap_or(0)
+    if amount == 0 {
+        env.storage().temporary().remove(&key);
+        return Ok(());
+    }
+
+    let now = env.ledger().sequence();
+    if expiration_ledger < now {
+        return Err(TokenError::InvalidExpiration);
+    }
+    let allowance = AllowanceValue {
+        amount,
+        expiration_ledger,
+    };
+    env.storage().temporary().set(&key, &allowance);
+    let live_for = expiration_ledger - now;
+    env.storage().temporary().extend_ttl(&key, live_for, live_for);
+    Ok(())
 }
 
-fn set_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+/// Delete a pre-v2 persistent allowance for the pair, if one is left over
+fn remove_legacy_allowance(env: &Env, from: &Address, spender: &Address) {
     let key = DataKey::Allowance(from.clone(), spender.clone());
-    if amount > 0 {
-        env.storage().persistent().set(&key, &amount);
-        env.storage()
-            .persistent()
-            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
-    } else {
+    if env.storage().persistent().has(&key) {
+        env.storage().persistent().remove(&key);
+    }
+}
+
+/// Deduct `amount` from an unexpired allowance, keeping its expiration
+fn spend_allowance(
+    env: &Env,
+    from: &Address,
+    spender: &Address,
+    amount: i128,
+) -> Result<(), TokenError> {
+    let allowance = read_allowance(env, from, spender);
+    if allowance.amount < amount {
+        return Err(TokenError::InsufficientAllowance);
+    }
+    set_allowance(
+        env,
+        from,
+        spender,
+        allowance.amount - amount,
+        allowance.expiration_ledger,
+    )
+}
+
+/// sXLM `holder` holds from each minter other than the primary
+fn get_issued(env: &Env, holder: &Address) -> Result<Map<Address, i128>, TokenError> {
+    let key = DataKey::Issued(holder.clone());
+    let mut issued: Map<Address, i128> = match env.storage().persistent().get(&key) {
+        Some(issued) => {
+            env.storage()
+                .persistent()
+                .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+            issued
+        }
+        None => return Ok(Map::new(env)),
+    };
+    // A minter that became primary owns its sXLM implicitly from then on
+    issued.remove(get_minter(env)?);
+    Ok(issued)
+}
+
+fn set_issued(env: &Env, holder: &Address, issued: &Map<Address, i128>) {
+    let key = DataKey::Issued(holder.clone());
+    if issued.is_empty() {
         env.storage().persistent().remove(&key);
+        return;
+    }
+    env.storage().persistent().set(&key, issued);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+fn sum_issued(issued: &Map<Address, i128>) -> i128 {
+    issued.values().iter().sum()
+}
+
+/// sXLM `holder` holds that `minter` issued. The primary minter owns
+/// whatever part of the balance no other minter is recorded as issuing.
+fn issued_balance(env: &Env, holder: &Address, minter: &Address) -> Result<i128, TokenError> {
+    let issued = get_issued(env, holder)?;
+    if *minter == get_minter(env)? {
+        return Ok(get_balance(env, holder) - sum_issued(&issued));
+    }
+    Ok(issued.get(minter.clone()).unwrap_or(0))
+}
+
+/// Record `amount` more of `minter`'s sXLM held by `holder`
+fn add_issued(
+    env: &Env,
+    holder: &Address,
+    minter: &Address,
+    amount: i128,
+) -> Result<(), TokenError> {
+    if *minter == get_minter(env)? {
+        return Ok(());
+    }
+    let mut issued = get_issued(env, holder)?;
+    let held = issued
+        .get(minter.clone())
+        .unwrap_or(0)
+        .checked_add(amount)
+        .ok_or(TokenError::Overflow)?;
+    issued.set(minter.clone(), held);
+    set_issued(env, holder, &issued);
+    Ok(())
+}
+
+/// Take `amount` of `holder`'s sXLM out of their issuance, primary minter's
+/// first and then other minters' in address order. Call before debiting the
+/// balance. Returns how much came from each minter.
+fn take_issued(
+    env: &Env,
+    holder: &Address,
+    amount: i128,
+) -> Result<Vec<(Address, i128)>, TokenError> {
+    let primary = get_minter(env)?;
+    let mut issued = get_issued(env, holder)?;
+    let mut parts = Vec::new(env);
+
+    let primary_part = (get_balance(env, holder) - sum_issued(&issued)).clamp(0, amount);
+    if primary_part > 0 {
+        parts.push_back((primary, primary_part));
     }
+    let mut rest = amount - primary_part;
+    for (minter, held) in issued.clone().iter() {
+        if rest == 0 {
+            break;
+        }
+        let part = held.min(rest);
+        if part == held {
+            issued.remove(minter.clone());
+        } else {
+            issued.set(minter.clone(), held - part);
+        }
+        parts.push_back((minter, part));
+        rest -= part;
+    }
+    set_issued(env, holder, &issued);
+    Ok(parts)
+}
+
+/// Move `amount` of `from`'s sXLM issuance to `to`. Call before balances move.
+fn transfer_issued(env: &Env, from: &Address, to: &Address, amount: i128) -> Result<(), TokenError> {
+    for (minter, part) in take_issued(env, from, amount)?.iter() {
+        add_issued(env, to, &minter, part)?;
+    }
+    Ok(())
+}
+
+/// Take `amount` of `holder`'s sXLM off the outstanding supply of the minters
+/// that issued it, for burns that do not go through a minter. Call before
+/// debiting the balance.
+fn release_issued(env: &Env, holder: &Address, amount: i128) -> Result<(), TokenError> {
+    let mut minters = get_minters(env);
+    for (minter, part) in take_issued(env, holder, amount)?.iter() {
+        // A removed minter no longer tracks its supply
+        if let Some(mut allowance) = minters.get(minter.clone()) {
+            allowance.outstanding = (allowance.outstanding - part).max(0);
+            minters.set(minter, allowance);
+        }
+    }
+    set_minters(env, &minters);
+    Ok(())
+}
+
+/// Burn `amount` of `from`'s sXLM on `minter`'s behalf, through `spender`'s
+/// allowance if one is given. A minter can only burn sXLM it issued, so its
+/// redemptions never free another minter's allowance.
+fn burn_issued(
+    env: &Env,
+    minter: &Address,
+    spender: Option<&Address>,
+    from: &Address,
+    amount: i128,
+) -> Result<(), TokenError> {
+    let mut allowance = require_minter(env, minter)?;
+    require_not_frozen(env, from)?;
+
+    if amount <= 0 {
+        return Err(TokenError::InvalidAmount);
+    }
+
+    if let Some(spender) = spender {
+        spender.require_auth();
+        spend_allowance(env, from, spender, amount)?;
+    }
+
+    let balance = get_balance(env, from);
+    if balance < amount {
+        return Err(TokenError::InsufficientBalance);
+    }
+    // A minter only redeems sXLM it issued, so sXLM minted cheaply at one
+    // vault cannot be redeemed at another with a higher rate
+    let held = issued_balance(env, from, minter)?;
+    if amount > held {
+        return Err(TokenError::ExceedsIssued);
+    }
+    if amount > allowance.outstanding {
+        return Err(TokenError::ExceedsOutstanding);
+    }
+    if *minter != get_minter(env)? {
+        let mut issued = get_issued(env, from)?;
+        if held == amount {
+            issued.remove(minter.clone());
+        } else {
+            issued.set(minter.clone(), held - amount);
+        }
+        set_issued(env, from, &issued);
+    }
+
+    set_balance(env, from, balance - amount);
+
+    let total = get_total_supply(env);
+    set_total_supply(env, total - amount);
+
+    allowance.outstanding -= amount;
+    let mut minters = get_minters(env);
+    minters.set(minter.clone(), allowance);
+    set_minters(env, &minters);
+
+    TokenUtils::new(env).events().burn(from.clone(), amount);
+    Ok(())
 }
 
 #[contract]
@@ -89,104 +682,576 @@ pub struct SxlmToken;
 
 #[contractimpl]
 impl SxlmToken {
-    /// Initialize the sXLM token
-    pub fn initialize(env: Env, admin: Address, minter: Address) {
+    /// Initialize the token, e.g. with `7, "Staked XLM", "sXLM"` for sXLM.
+    /// Decimals cannot change afterwards; name and symbol can through
+    /// `set_metadata`.
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        minter: Address,
+        decimal: u32,
+        name: String,
+        symbol: String,
+    ) -> Result<(), TokenError> {
         if env.storage().instance().has(&DataKey::Admin) {
-            panic!("Already initialized");
+            return Err(TokenError::AlreadyInitialized);
+        }
+        if decimal > MAX_DECIMALS {
+            return Err(TokenError::InvalidMetadata);
         }
+        validate_name_and_symbol(&name, &symbol)?;
 
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::Minter, &minter);
-        env.storage().instance().set(&DataKey::Name, &String::from_str(&env, "Staked XLM"));
-        env.storage().instance().set(&DataKey::Symbol, &String::from_str(&env, "sXLM"));
-        env.storage().instance().set(&DataKey::Decimals, &7u32);
+        let mut minters = Map::new(&env);
+        minters.set(
+            minter,
+            MinterAllowance {
+                cap: UNCAPPED,
+                outstanding: 0,
+            },
+        );
+        set_minters(&env, &minters);
+        write_metadata(&env, decimal, name, symbol);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
         set_total_supply(&env, 0);
         extend_instance_ttl(&env);
+        Ok(())
     }
 
-    /// Mint sXLM tokens (only callable by vault/minter)
-    pub fn mint(env: Env, to: Address, amount: i128) {
+    /// Run the migration steps that bring storage up to `target_version`
+    /// (admin only). `target_version` must be the version of the running
+    /// code, so each upgrade migrates exactly once.
+    pub fn migrate(env: Env, target_version: u32) -> Result<(), TokenError> {
         extend_instance_ttl(&env);
+        let admin = get_admin(&env)?;
+        admin.require_auth();
 
-        let minter: Address = env.storage().instance().get(&DataKey::Minter).unwrap();
-        minter.require_auth();
+        let from_version = get_schema_version(&env);
+        if target_version != SCHEMA_VERSION || from_version >= target_version {
+            return Err(TokenError::InvalidSchemaVersion);
+        }
 
-        if amount <= 0 {
-            panic!("Amount must be positive");
+        for version in (from_version + 1)..=target_version {
+            migrate_step(&env, version)?;
         }
+        env.storage().instance().set(&DataKey::SchemaVersion, &target_version);
 
-        let balance = get_balance(&env, &to);
-        set_balance(&env, &to, balance.checked_add(amount).expect("Overflow"));
+        env.events().publish(
+            (Symbol::new(&env, "migrated"),),
+            (from_version, target_version),
+        );
+        Ok(())
+    }
 
-        let total = get_total_supply(&env);
-        set_total_supply(&env, total.checked_add(amount).expect("Overflow"));
+    /// Rename the token (admin only). Decimals are fixed at initialization.
+    pub fn set_metadata(env: Env, name: String, symbol: String) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
 
-        TokenUtils::new(&env).events().mint(minter, to, amount);
+        validate_name_and_symbol(&name, &symbol)?;
+        let decimal = read_metadata(&env).decimal;
+        write_metadata(&env, decimal, name.clone(), symbol.clone());
+
+        env.events().publish(
+            (Symbol::new(&env, "metadata_updated"),),
+            (name, symbol),
+        );
+        Ok(())
     }
 
-    /// Burn sXLM tokens (only callable by vault/minter)
-    pub fn burn(env: Env, from: Address, amount: i128) {
+    /// Get the storage schema version
+    pub fn schema_version(env: Env) -> u32 {
         extend_instance_ttl(&env);
+        get_schema_version(&env)
+    }
 
-        let minter: Address = env.storage().instance().get(&DataKey::Minter).unwrap();
-        minter.require_auth();
+    /// Mint sXLM tokens (minters only), within the minter's allowance
+    pub fn mint(env: Env, minter: Address, to: Address, amount: i128) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let mut allowance = require_minter(&env, &minter)?;
 
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(TokenError::InvalidAmount);
         }
 
-        let balance = get_balance(&env, &from);
-        if balance < amount {
-            panic!("Insufficient balance");
+        allowance.outstanding = allowance
+            .outstanding
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+        if allowance.outstanding > allowance.cap {
+            return Err(TokenError::MinterCapExceeded);
         }
+        let mut minters = get_minters(&env);
+        minters.set(minter.clone(), allowance);
+        set_minters(&env, &minters);
+        add_issued(&env, &to, &minter, amount)?;
 
-        set_balance(&env, &from, balance - amount);
+        let balance = get_balance(&env, &to);
+        set_balance(&env, &to, balance.checked_add(amount).ok_or(TokenError::Overflow)?);
 
         let total = get_total_supply(&env);
-        set_total_supply(&env, total - amount);
+        set_total_supply(&env, total.checked_add(amount).ok_or(TokenError::Overflow)?);
 
-        TokenUtils::new(&env).events().burn(from, amount);
+        TokenUtils::new(&env).events().mint(minter, to, amount);
+        Ok(())
+    }
+
+    /// Burn sXLM on redemption (minters only), freeing the minter's allowance
+    pub fn minter_burn(
+        env: Env,
+        minter: Address,
+        from: Address,
+        amount: i128,
+    ) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        burn_issued(&env, &minter, None, &from, amount)
     }
 
-    /// User burn - user can burn their own tokens
-    pub fn burn_self(env: Env, from: Address, amount: i128) {
+    /// Burn sXLM on redemption through `spender`'s allowance (minters only),
+    /// e.g. a vault redeeming on an approved operator's behalf
+    pub fn minter_burn_from(
+        env: Env,
+        minter: Address,
+        spender: Address,
+        from: Address,
+        amount: i128,
+    ) -> Result<(), TokenError> {
         extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        burn_issued(&env, &minter, Some(&spender), &from, amount)
+    }
+
+    /// Burn sXLM tokens (only callable by vault/minter). Same as
+    /// `minter_burn` for the primary minter.
+    pub fn burn(env: Env, from: Address, amount: i128) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let minter = get_minter(&env)?;
+        burn_issued(&env, &minter, None, &from, amount)
+    }
+
+    /// User burn - user can burn their own tokens. The burned sXLM comes off
+    /// its issuing minters' outstanding supply, so their remaining holders'
+    /// sXLM keeps its claim on the minter's assets.
+    pub fn burn_self(env: Env, from: Address, amount: i128) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
         from.require_auth();
+        require_not_frozen(&env, &from)?;
 
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(TokenError::InvalidAmount);
         }
 
         let balance = get_balance(&env, &from);
         if balance < amount {
-            panic!("Insufficient balance");
+            return Err(TokenError::InsufficientBalance);
         }
+        release_issued(&env, &from, amount)?;
 
         set_balance(&env, &from, balance - amount);
         let total = get_total_supply(&env);
         set_total_supply(&env, total - amount);
 
         TokenUtils::new(&env).events().burn(from, amount);
+        Ok(())
+    }
+
+    /// Place or lift `caller`'s pause on holder-to-holder transfers (admin or
+    /// any minter). Each caller holds its own pause and transfers resume only
+    /// once every pause is lifted, so one vault unpausing cannot override
+    /// another vault or the admin. Minting and burning are governed by each
+    /// vault's own pause flags.
+    pub fn set_transfers_paused(env: Env, caller: Address, paused: bool) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        caller.require_auth();
+        if caller != get_admin(&env)? && !get_minters(&env).contains_key(caller.clone()) {
+            return Err(TokenError::Unauthorized);
+        }
+
+        hold_transfer_pause(&env, &caller, paused);
+        env.events().publish(
+            (Symbol::new(&env, "transfers_paused"), caller),
+            paused,
+        );
+        Ok(())
+    }
+
+    /// Check whether transfers are paused by anyone
+    pub fn transfers_paused(env: Env) -> bool {
+        extend_instance_ttl(&env);
+        transfers_paused(&env)
+    }
+
+    /// The admin and minters currently holding a transfer pause
+    pub fn transfer_pauses(env: Env) -> Vec<Address> {
+        extend_instance_ttl(&env);
+        get_transfer_pauses(&env)
+    }
+
+    /// Set or clear `minter`'s transfer hook (admin only). Every minter's
+    /// hook is notified of every holder-to-holder transfer through
+    /// `on_transfer(from, to, amount)`, since sXLM from any minter is fungible.
+    pub fn set_transfer_hook(
+        env: Env,
+        minter: Address,
+        hook: Option<Address>,
+    ) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        if !get_minters(&env).contains_key(minter.clone()) {
+            return Err(TokenError::NotMinter);
+        }
+        let mut hooks = get_transfer_hooks(&env);
+        match &hook {
+            Some(hook) => hooks.set(minter.clone(), hook.clone()),
+            None => {
+                hooks.remove(minter.clone());
+            }
+        }
+        set_transfer_hooks(&env, &hooks);
+        env.events().publish(
+            (Symbol::new(&env, "transfer_hook"), minter),
+            hook,
+        );
+        Ok(())
+    }
+
+    /// Get `minter`'s transfer hook, if one is set
+    pub fn transfer_hook(env: Env, minter: Address) -> Option<Address> {
+        extend_instance_ttl(&env);
+        get_transfer_hooks(&env).get(minter)
+    }
+
+    /// Set or clear the compliance officer, who can freeze and unfreeze
+    /// holders alongside the admin (admin only)
+    pub fn set_compliance(env: Env, compliance: Option<Address>) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        match &compliance {
+            Some(compliance) => env.storage().instance().set(&DataKey::Compliance, compliance),
+            None => env.storage().instance().remove(&DataKey::Compliance),
+        }
+        env.events().publish(
+            (Symbol::new(&env, "compliance_set"),),
+            compliance,
+        );
+        Ok(())
+    }
+
+    /// Get the compliance officer, if one is set
+    pub fn compliance(env: Env) -> Option<Address> {
+        extend_instance_ttl(&env);
+        env.storage().instance().get(&DataKey::Compliance)
+    }
+
+    /// Freeze `id` (admin or compliance). A frozen holder cannot transfer,
+    /// be spent from, burn, or redeem through the vault until unfrozen.
+    pub fn freeze(env: Env, caller: Address, id: Address) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_compliance(&env, &caller)?;
+
+        let key = DataKey::Frozen(id.clone());
+        env.storage().persistent().set(&key, &true);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+
+        env.events().publish(
+            (Symbol::new(&env, "frozen"), id),
+            caller,
+        );
+        Ok(())
+    }
+
+    /// Lift a freeze on `id` (admin or compliance)
+    pub fn unfreeze(env: Env, caller: Address, id: Address) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_compliance(&env, &caller)?;
+
+        env.storage().persistent().remove(&DataKey::Frozen(id.clone()));
+        env.events().publish(
+            (Symbol::new(&env, "unfrozen"), id),
+            caller,
+        );
+        Ok(())
     }
 
-    /// Get the minter (vault) address
-    pub fn minter(env: Env) -> Address {
+    /// Check whether `id` is frozen
+    pub fn is_frozen(env: Env, id: Address) -> bool {
         extend_instance_ttl(&env);
-        env.storage().instance().get(&DataKey::Minter).unwrap()
+        is_frozen(&env, &id)
     }
 
-    /// Update minter (admin only)
-    pub fn set_minter(env: Env, new_minter: Address) {
+    /// Add a minter allowed up to `cap` sXLM outstanding (admin only)
+    pub fn add_minter(env: Env, minter: Address, cap: i128) -> Result<(), TokenError> {
         extend_instance_ttl(&env);
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
         admin.require_auth();
+
+        if cap < 0 {
+            return Err(TokenError::InvalidAmount);
+        }
+        let mut minters = get_minters(&env);
+        if minters.contains_key(minter.clone()) {
+            return Err(TokenError::MinterExists);
+        }
+        minters.set(minter.clone(), MinterAllowance { cap, outstanding: 0 });
+        set_minters(&env, &minters);
+
+        env.events().publish(
+            (Symbol::new(&env, "minter_added"),),
+            (minter, cap),
+        );
+        Ok(())
+    }
+
+    /// Remove a minter (admin only). The primary minter can only be replaced
+    /// through `propose_minter`.
+    pub fn remove_minter(env: Env, minter: Address) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        if minter == get_minter(&env)? {
+            return Err(TokenError::PrimaryMinter);
+        }
+        let mut minters = get_minters(&env);
+        if minters.remove(minter.clone()).is_none() {
+            return Err(TokenError::NotMinter);
+        }
+        set_minters(&env, &minters);
+
+        // A removed minter's pause and hook go with it
+        hold_transfer_pause(&env, &minter, false);
+        let mut hooks = get_transfer_hooks(&env);
+        if hooks.remove(minter.clone()).is_some() {
+            set_transfer_hooks(&env, &hooks);
+        }
+
+        env.events().publish(
+            (Symbol::new(&env, "minter_removed"),),
+            minter,
+        );
+        Ok(())
+    }
+
+    /// Change a minter's cap (admin only). A cap below what the minter
+    /// already has outstanding only stops further minting.
+    pub fn set_minter_cap(env: Env, minter: Address, cap: i128) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        if cap < 0 {
+            return Err(TokenError::InvalidAmount);
+        }
+        let mut minters = get_minters(&env);
+        let mut allowance = minters.get(minter.clone()).ok_or(TokenError::NotMinter)?;
+        allowance.cap = cap;
+        minters.set(minter.clone(), allowance);
+        set_minters(&env, &minters);
+
+        env.events().publish(
+            (Symbol::new(&env, "minter_cap_set"),),
+            (minter, cap),
+        );
+        Ok(())
+    }
+
+    /// List every minter with its cap, outstanding supply and headroom
+    pub fn minters(env: Env) -> Vec<MinterInfo> {
+        extend_instance_ttl(&env);
+        let mut out = Vec::new(&env);
+        for (minter, allowance) in get_minters(&env).iter() {
+            out.push_back(MinterInfo {
+                minter,
+                cap: allowance.cap,
+                outstanding: allowance.outstanding,
+                headroom: headroom(&allowance),
+            });
+        }
+        out
+    }
+
+    /// sXLM `minter` has issued and not yet burned (0 for non-minters)
+    pub fn minter_outstanding(env: Env, minter: Address) -> i128 {
+        extend_instance_ttl(&env);
+        get_minters(&env)
+            .get(minter)
+            .map(|allowance| allowance.outstanding)
+            .unwrap_or(0)
+    }
+
+    /// sXLM `id` holds that `minter` issued, the most `minter` can redeem
+    /// from `id`
+    pub fn issued_balance(env: Env, id: Address, minter: Address) -> Result<i128, TokenError> {
+        extend_instance_ttl(&env);
+        issued_balance(&env, &id, &minter)
+    }
+
+    /// How much more `minter` may mint (0 for non-minters)
+    pub fn minter_headroom(env: Env, minter: Address) -> i128 {
+        extend_instance_ttl(&env);
+        get_minters(&env)
+            .get(minter)
+            .map(|allowance| headroom(&allowance))
+            .unwrap_or(0)
+    }
+
+    /// Get the primary minter (vault) address
+    pub fn minter(env: Env) -> Result<Address, TokenError> {
+        extend_instance_ttl(&env);
+        get_minter(&env)
+    }
+
+    /// Propose a new primary minter (admin only). Takes effect once
+    /// `new_minter` calls `accept_minter`, optionally by `expires_ledger`.
+    pub fn propose_minter(
+        env: Env,
+        new_minter: Address,
+        expires_ledger: Option<u32>,
+    ) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        propose_transfer(&env, &DataKey::PendingMinter, &new_minter, expires_ledger);
+        env.events().publish(
+            (Symbol::new(&env, "minter_proposed"),),
+            (new_minter, expires_ledger),
+        );
+        Ok(())
+    }
+
+    /// Accept a pending minter proposal (proposed minter only). The new
+    /// primary replaces the old one in the minter set.
+    pub fn accept_minter(env: Env) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let old_minter = get_minter(&env)?;
+        let new_minter = take_pending_transfer(&env, &DataKey::PendingMinter)?;
         env.storage().instance().set(&DataKey::Minter, &new_minter);
+
+        // The new primary takes over the old one's allowance and supply
+        let mut minters = get_minters(&env);
+        let mut allowance = minters.get(old_minter.clone()).ok_or(TokenError::NotMinter)?;
+        minters.remove(old_minter.clone());
+        if let Some(existing) = minters.get(new_minter.clone()) {
+            allowance.outstanding = allowance
+                .outstanding
+                .checked_add(existing.outstanding)
+                .ok_or(TokenError::Overflow)?;
+        }
+        minters.set(new_minter.clone(), allowance);
+        set_minters(&env, &minters);
+
+        // The pause carries over too. The hook is dropped with the old
+        // minter; the admin points the new primary at its own hook.
+        let pauses = get_transfer_pauses(&env);
+        if pauses.contains(&old_minter) {
+            hold_transfer_pause(&env, &old_minter, false);
+            hold_transfer_pause(&env, &new_minter, true);
+        }
+        let mut hooks = get_transfer_hooks(&env);
+        if hooks.remove(old_minter.clone()).is_some() {
+            set_transfer_hooks(&env, &hooks);
+        }
+
+        env.events().publish(
+            (Symbol::new(&env, "minter_transferred"),),
+            (old_minter, new_minter),
+        );
+        Ok(())
+    }
+
+    /// Withdraw a pending minter proposal (admin only)
+    pub fn cancel_minter_transfer(env: Env) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+        cancel_pending_transfer(&env, &DataKey::PendingMinter)
+    }
+
+    /// Get the pending minter proposal, if any
+    pub fn pending_minter(env: Env) -> Option<PendingTransfer> {
+        extend_instance_ttl(&env);
+        env.storage().instance().get(&DataKey::PendingMinter)
     }
 
     /// Get admin address
-    pub fn admin(env: Env) -> Address {
+    pub fn admin(env: Env) -> Result<Address, TokenError> {
         extend_instance_ttl(&env);
-        env.storage().instance().get(&DataKey::Admin).unwrap()
+        get_admin(&env)
+    }
+
+    /// Propose a new admin (admin only). Takes effect once `new_admin`
+    /// calls `accept_admin`, optionally by `expires_ledger`.
+    pub fn propose_admin(
+        env: Env,
+        new_admin: Address,
+        expires_ledger: Option<u32>,
+    ) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        propose_transfer(&env, &DataKey::PendingAdmin, &new_admin, expires_ledger);
+        env.events().publish(
+            (Symbol::new(&env, "admin_proposed"),),
+            (new_admin, expires_ledger),
+        );
+        Ok(())
+    }
+
+    /// Accept a pending admin proposal (proposed admin only)
+    pub fn accept_admin(env: Env) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let old_admin = get_admin(&env)?;
+        let new_admin = take_pending_transfer(&env, &DataKey::PendingAdmin)?;
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+
+        env.events().publish(
+            (Symbol::new(&env, "admin_transferred"),),
+            (old_admin, new_admin),
+        );
+        Ok(())
+    }
+
+    /// Withdraw a pending admin proposal (admin only)
+    pub fn cancel_admin_transfer(env: Env) -> Result<(), TokenError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+        cancel_pending_transfer(&env, &DataKey::PendingAdmin)
+    }
+
+    /// Get the pending admin proposal, if any
+    pub fn pending_admin(env: Env) -> Option<PendingTransfer> {
+        extend_instance_ttl(&env);
+        env.storage().instance().get(&DataKey::PendingAdmin)
     }
 
     /// Get total supply of sXLM
@@ -197,21 +1262,34 @@ impl SxlmToken {
 
     // ============ Standard Token Interface ============
 
+    /// Remaining allowance, or 0 once `expiration_ledger` has passed
     pub fn allowance(env: Env, from: Address, spender: Address) -> i128 {
         extend_instance_ttl(&env);
         get_allowance(&env, &from, &spender)
     }
 
-    pub fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+    /// Let `spender` spend up to `amount` of `from`'s sXLM through
+    /// `expiration_ledger` (inclusive), replacing any existing allowance.
+    /// A non-zero amount needs an expiration no earlier than the current ledger.
+    pub fn approve(
+        env: Env,
+        from: Address,
+        spender: Address,
+        amount: i128,
+        expiration_ledger: u32,
+    ) -> Result<(), TokenError> {
         extend_instance_ttl(&env);
+        require_current_schema(&env)?;
         from.require_auth();
 
         if amount < 0 {
-            panic!("Amount cannot be negative");
+            return Err(TokenError::NegativeAmount);
         }
 
-        set_allowance(&env, &from, &spender, amount);
+        remove_legacy_allowance(&env, &from, &spender);
+        set_allowance(&env, &from, &spender, amount, expiration_ledger)?;
         TokenUtils::new(&env).events().approve(from, spender, amount, expiration_ledger);
+        Ok(())
     }
 
     pub fn balance(env: Env, id: Address) -> i128 {
@@ -219,89 +1297,117 @@ impl SxlmToken {
         get_balance(&env, &id)
     }
 
-    pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+    pub fn transfer(env: Env, from: Address, to: Address, amount: i128) -> Result<(), TokenError> {
         extend_instance_ttl(&env);
+        require_current_schema(&env)?;
         from.require_auth();
 
+        if transfers_paused(&env) {
+            return Err(TokenError::TransfersPaused);
+        }
+        require_not_frozen(&env, &from)?;
+
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(TokenError::InvalidAmount);
         }
 
         let from_balance = get_balance(&env, &from);
         if from_balance < amount {
-            panic!("Insufficient balance");
+            return Err(TokenError::InsufficientBalance);
         }
+        transfer_issued(&env, &from, &to, amount)?;
 
         set_balance(&env, &from, from_balance - amount);
-        set_balance(&env, &to, get_balance(&env, &to).checked_add(amount).expect("Overflow"));
+        let to_balance = get_balance(&env, &to)
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+        set_balance(&env, &to, to_balance);
 
+        notify_transfer(&env, &from, &to, amount);
         TokenUtils::new(&env).events().transfer(from, to, amount);
+        Ok(())
     }
 
-    pub fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+    pub fn transfer_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), TokenError> {
         extend_instance_ttl(&env);
+        require_current_schema(&env)?;
         spender.require_auth();
 
-        if amount <= 0 {
-            panic!("Amount must be positive");
+        if transfers_paused(&env) {
+            return Err(TokenError::TransfersPaused);
         }
+        require_not_frozen(&env, &from)?;
 
-        let allowance = get_allowance(&env, &from, &spender);
-        if allowance < amount {
-            panic!("Insufficient allowance");
+        if amount <= 0 {
+            return Err(TokenError::InvalidAmount);
         }
 
+        spend_allowance(&env, &from, &spender, amount)?;
+
         let from_balance = get_balance(&env, &from);
         if from_balance < amount {
-            panic!("Insufficient balance");
+            return Err(TokenError::InsufficientBalance);
         }
+        transfer_issued(&env, &from, &to, amount)?;
 
-        set_allowance(&env, &from, &spender, allowance - amount);
         set_balance(&env, &from, from_balance - amount);
-        set_balance(&env, &to, get_balance(&env, &to).checked_add(amount).expect("Overflow"));
+        let to_balance = get_balance(&env, &to)
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+        set_balance(&env, &to, to_balance);
 
+        notify_transfer(&env, &from, &to, amount);
         TokenUtils::new(&env).events().transfer(from, to, amount);
+        Ok(())
     }
 
-    pub fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+    pub fn burn_from(env: Env, spender: Address, from: Address, amount: i128) -> Result<(), TokenError> {
         extend_instance_ttl(&env);
+        require_current_schema(&env)?;
         spender.require_auth();
+        require_not_frozen(&env, &from)?;
 
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(TokenError::InvalidAmount);
         }
 
-        let allowance = get_allowance(&env, &from, &spender);
-        if allowance < amount {
-            panic!("Insufficient allowance");
-        }
+        spend_allowance(&env, &from, &spender, amount)?;
 
         let balance = get_balance(&env, &from);
         if balance < amount {
-            panic!("Insufficient balance");
+            return Err(TokenError::InsufficientBalance);
         }
+        release_issued(&env, &from, amount)?;
 
-        set_allowance(&env, &from, &spender, allowance - amount);
         set_balance(&env, &from, balance - amount);
         let total = get_total_supply(&env);
         set_total_supply(&env, total - amount);
 
         TokenUtils::new(&env).events().burn(from, amount);
+        Ok(())
     }
 
-    pub fn decimals(_env: Env) -> u32 {
-        7
+    pub fn decimals(env: Env) -> u32 {
+        extend_instance_ttl(&env);
+        read_metadata(&env).decimal
     }
 
     pub fn name(env: Env) -> String {
         extend_instance_ttl(&env);
-        env.storage().instance().get(&DataKey::Name).unwrap()
+        read_metadata(&env).name
     }
 
     pub fn symbol(env: Env) -> String {
         extend_instance_ttl(&env);
-        env.storage().instance().get(&DataKey::Symbol).unwrap()
+        read_metadata(&env).symbol
     }
 }
 
+mod conformance;
 mod test;
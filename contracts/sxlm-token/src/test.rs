@@ -1,7 +1,26 @@
 #![cfg(test)]
 
-use crate::{SxlmToken, SxlmTokenClient};
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use crate::{SxlmToken, SxlmTokenClient, TokenError};
+use soroban_sdk::{
+    contract, contractimpl,
+    testutils::{Address as _, Ledger},
+    Address, Env, String,
+};
+
+/// Transfer hook that records the last notification it received
+#[contract]
+pub struct RecordingHook;
+
+#[contractimpl]
+impl RecordingHook {
+    pub fn on_transfer(env: Env, from: Address, to: Address, amount: i128) {
+        env.storage().instance().set(&0u32, &(from, to, amount));
+    }
+
+    pub fn last(env: Env) -> Option<(Address, Address, i128)> {
+        env.storage().instance().get(&0u32)
+    }
+}
 
 fn setup_test() -> (Env, SxlmTokenClient<'static>, Address, Address, Address) {
     let env = Env::default();
@@ -14,7 +33,13 @@ fn setup_test() -> (Env, SxlmTokenClient<'static>, Address, Address, Address) {
     let minter = Address::generate(&env);
     let user = Address::generate(&env);
 
-    client.initialize(&admin, &minter);
+    client.initialize(
+        &admin,
+        &minter,
+        &7,
+        &String::from_str(&env, "Staked XLM"),
+        &String::from_str(&env, "sXLM"),
+    );
 
     (env, client, admin, minter, user)
 }
@@ -32,19 +57,68 @@ fn test_initialize() {
 }
 
 #[test]
-#[should_panic(expected = "Already initialized")]
 fn test_cannot_reinitialize() {
     let (env, client, _admin, _minter, _user) = setup_test();
     let new_admin = Address::generate(&env);
     let new_minter = Address::generate(&env);
-    client.initialize(&new_admin, &new_minter);
+    assert_eq!(
+        client.try_initialize(
+            &new_admin,
+            &new_minter,
+            &7,
+            &String::from_str(&env, "Staked XLM"),
+            &String::from_str(&env, "sXLM"),
+        ),
+        Err(Ok(TokenError::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn test_initialize_variant_metadata() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let client = SxlmTokenClient::new(&env, &env.register(SxlmToken, ()));
+    let admin = Address::generate(&env);
+    let name = String::from_str(&env, "Staked XLM Testnet");
+    let symbol = String::from_str(&env, "sXLM-t");
+
+    assert_eq!(
+        client.try_initialize(&admin, &admin, &19, &name, &symbol),
+        Err(Ok(TokenError::InvalidMetadata))
+    );
+    assert_eq!(
+        client.try_initialize(&admin, &admin, &7, &name, &String::from_str(&env, "")),
+        Err(Ok(TokenError::InvalidMetadata))
+    );
+
+    client.initialize(&admin, &admin, &6, &name, &symbol);
+    assert_eq!(client.decimals(), 6);
+    assert_eq!(client.name(), name);
+    assert_eq!(client.symbol(), symbol);
+}
+
+#[test]
+fn test_set_metadata() {
+    let (env, client, _admin, _minter, _user) = setup_test();
+    let name = String::from_str(&env, "Liquid Staked XLM");
+    let symbol = String::from_str(&env, "lsXLM");
+
+    assert_eq!(
+        client.try_set_metadata(&String::from_str(&env, ""), &symbol),
+        Err(Ok(TokenError::InvalidMetadata))
+    );
+
+    client.set_metadata(&name, &symbol);
+    assert_eq!(client.name(), name);
+    assert_eq!(client.symbol(), symbol);
+    assert_eq!(client.decimals(), 7);
 }
 
 #[test]
 fn test_mint() {
-    let (_env, client, _admin, _minter, user) = setup_test();
+    let (_env, client, _admin, minter, user) = setup_test();
 
-    client.mint(&user, &1000_0000000);
+    client.mint(&minter, &user, &1000_0000000);
 
     assert_eq!(client.balance(&user), 1000_0000000);
     assert_eq!(client.total_supply(), 1000_0000000);
@@ -52,11 +126,11 @@ fn test_mint() {
 
 #[test]
 fn test_mint_multiple_users() {
-    let (env, client, _admin, _minter, user1) = setup_test();
+    let (env, client, _admin, minter, user1) = setup_test();
     let user2 = Address::generate(&env);
 
-    client.mint(&user1, &1000_0000000);
-    client.mint(&user2, &500_0000000);
+    client.mint(&minter, &user1, &1000_0000000);
+    client.mint(&minter, &user2, &500_0000000);
 
     assert_eq!(client.balance(&user1), 1000_0000000);
     assert_eq!(client.balance(&user2), 500_0000000);
@@ -65,9 +139,9 @@ fn test_mint_multiple_users() {
 
 #[test]
 fn test_burn() {
-    let (_env, client, _admin, _minter, user) = setup_test();
+    let (_env, client, _admin, minter, user) = setup_test();
 
-    client.mint(&user, &1000_0000000);
+    client.mint(&minter, &user, &1000_0000000);
     client.burn(&user, &400_0000000);
 
     assert_eq!(client.balance(&user), 600_0000000);
@@ -75,20 +149,22 @@ fn test_burn() {
 }
 
 #[test]
-#[should_panic(expected = "Insufficient balance")]
 fn test_burn_insufficient_balance() {
-    let (_env, client, _admin, _minter, user) = setup_test();
+    let (_env, client, _admin, minter, user) = setup_test();
 
-    client.mint(&user, &100_0000000);
-    client.burn(&user, &200_0000000);
+    client.mint(&minter, &user, &100_0000000);
+    assert_eq!(
+        client.try_burn(&user, &200_0000000),
+        Err(Ok(TokenError::InsufficientBalance))
+    );
 }
 
 #[test]
 fn test_transfer() {
-    let (env, client, _admin, _minter, user1) = setup_test();
+    let (env, client, _admin, minter, user1) = setup_test();
     let user2 = Address::generate(&env);
 
-    client.mint(&user1, &1000_0000000);
+    client.mint(&minter, &user1, &1000_0000000);
     client.transfer(&user1, &user2, &300_0000000);
 
     assert_eq!(client.balance(&user1), 700_0000000);
@@ -97,22 +173,24 @@ fn test_transfer() {
 }
 
 #[test]
-#[should_panic(expected = "Insufficient balance")]
 fn test_transfer_insufficient_balance() {
-    let (env, client, _admin, _minter, user1) = setup_test();
+    let (env, client, _admin, minter, user1) = setup_test();
     let user2 = Address::generate(&env);
 
-    client.mint(&user1, &100_0000000);
-    client.transfer(&user1, &user2, &200_0000000);
+    client.mint(&minter, &user1, &100_0000000);
+    assert_eq!(
+        client.try_transfer(&user1, &user2, &200_0000000),
+        Err(Ok(TokenError::InsufficientBalance))
+    );
 }
 
 #[test]
 fn test_approve_and_transfer_from() {
-    let (env, client, _admin, _minter, user1) = setup_test();
+    let (env, client, _admin, minter, user1) = setup_test();
     let user2 = Address::generate(&env);
     let spender = Address::generate(&env);
 
-    client.mint(&user1, &1000_0000000);
+    client.mint(&minter, &user1, &1000_0000000);
     client.approve(&user1, &spender, &500_0000000, &1000);
 
     assert_eq!(client.allowance(&user1, &spender), 500_0000000);
@@ -125,23 +203,25 @@ fn test_approve_and_transfer_from() {
 }
 
 #[test]
-#[should_panic(expected = "Insufficient allowance")]
 fn test_transfer_from_insufficient_allowance() {
-    let (env, client, _admin, _minter, user1) = setup_test();
+    let (env, client, _admin, minter, user1) = setup_test();
     let user2 = Address::generate(&env);
     let spender = Address::generate(&env);
 
-    client.mint(&user1, &1000_0000000);
+    client.mint(&minter, &user1, &1000_0000000);
     client.approve(&user1, &spender, &100_0000000, &1000);
 
-    client.transfer_from(&spender, &user1, &user2, &200_0000000);
+    assert_eq!(
+        client.try_transfer_from(&spender, &user1, &user2, &200_0000000),
+        Err(Ok(TokenError::InsufficientAllowance))
+    );
 }
 
 #[test]
 fn test_burn_self() {
-    let (_env, client, _admin, _minter, user) = setup_test();
+    let (_env, client, _admin, minter, user) = setup_test();
 
-    client.mint(&user, &1000_0000000);
+    client.mint(&minter, &user, &1000_0000000);
     client.burn_self(&user, &400_0000000);
 
     assert_eq!(client.balance(&user), 600_0000000);
@@ -149,38 +229,81 @@ fn test_burn_self() {
 }
 
 #[test]
-fn test_set_minter() {
-    let (env, client, _admin, _minter, _user) = setup_test();
+fn test_two_step_minter_transfer() {
+    let (env, client, _admin, minter, user) = setup_test();
     let new_minter = Address::generate(&env);
+    client.mint(&minter, &user, &100);
+    client.set_transfers_paused(&minter, &true);
+    client.set_transfer_hook(&minter, &Some(Address::generate(&env)));
 
-    client.set_minter(&new_minter);
+    client.propose_minter(&new_minter, &None);
+    assert_eq!(client.minter(), minter);
+    assert_eq!(client.pending_minter().unwrap().address, new_minter);
 
+    client.accept_minter();
     assert_eq!(client.minter(), new_minter);
+    assert_eq!(client.pending_minter(), None);
+    assert_eq!(client.try_accept_minter(), Err(Ok(TokenError::NoPendingTransfer)));
+
+    // The new primary inherits the old one's outstanding supply
+    let minters = client.minters();
+    assert_eq!(minters.len(), 1);
+    assert_eq!(minters.get(0).unwrap().minter, new_minter);
+    assert_eq!(minters.get(0).unwrap().outstanding, 100);
+    assert_eq!(client.try_mint(&minter, &user, &1), Err(Ok(TokenError::NotMinter)));
+
+    // ...and its transfer pause, but not its hook
+    assert_eq!(client.transfer_pauses(), soroban_sdk::vec![&env, new_minter.clone()]);
+    assert_eq!(client.transfer_hook(&minter), None);
+    assert_eq!(client.transfer_hook(&new_minter), None);
+}
+
+#[test]
+fn test_two_step_admin_transfer() {
+    let (env, client, admin, _minter, _user) = setup_test();
+    let new_admin = Address::generate(&env);
+
+    // Cancelled proposals cannot be accepted
+    client.propose_admin(&new_admin, &None);
+    client.cancel_admin_transfer();
+    assert_eq!(client.try_accept_admin(), Err(Ok(TokenError::NoPendingTransfer)));
+    assert_eq!(client.admin(), admin);
+
+    // Expired proposals cannot be accepted
+    let expiry = env.ledger().sequence() + 10;
+    client.propose_admin(&new_admin, &Some(expiry));
+    env.ledger().with_mut(|l| l.sequence_number = expiry + 1);
+    assert_eq!(client.try_accept_admin(), Err(Ok(TokenError::TransferExpired)));
+
+    client.propose_admin(&new_admin, &Some(expiry + 10));
+    client.accept_admin();
+    assert_eq!(client.admin(), new_admin);
 }
 
 #[test]
-#[should_panic(expected = "Amount must be positive")]
 fn test_mint_zero_amount() {
-    let (_env, client, _admin, _minter, user) = setup_test();
-    client.mint(&user, &0);
+    let (_env, client, _admin, minter, user) = setup_test();
+    assert_eq!(client.try_mint(&minter, &user, &0), Err(Ok(TokenError::InvalidAmount)));
 }
 
 #[test]
-#[should_panic(expected = "Amount must be positive")]
 fn test_transfer_zero_amount() {
-    let (env, client, _admin, _minter, user1) = setup_test();
+    let (env, client, _admin, minter, user1) = setup_test();
     let user2 = Address::generate(&env);
 
-    client.mint(&user1, &1000_0000000);
-    client.transfer(&user1, &user2, &0);
+    client.mint(&minter, &user1, &1000_0000000);
+    assert_eq!(
+        client.try_transfer(&user1, &user2, &0),
+        Err(Ok(TokenError::InvalidAmount))
+    );
 }
 
 #[test]
 fn test_approve_zero_removes_allowance() {
-    let (env, client, _admin, _minter, user1) = setup_test();
+    let (env, client, _admin, minter, user1) = setup_test();
     let spender = Address::generate(&env);
 
-    client.mint(&user1, &1000_0000000);
+    client.mint(&minter, &user1, &1000_0000000);
     client.approve(&user1, &spender, &500_0000000, &1000);
     assert_eq!(client.allowance(&user1, &spender), 500_0000000);
 
@@ -190,10 +313,10 @@ fn test_approve_zero_removes_allowance() {
 
 #[test]
 fn test_burn_from() {
-    let (env, client, _admin, _minter, user1) = setup_test();
+    let (env, client, _admin, minter, user1) = setup_test();
     let spender = Address::generate(&env);
 
-    client.mint(&user1, &1000_0000000);
+    client.mint(&minter, &user1, &1000_0000000);
     client.approve(&user1, &spender, &500_0000000, &1000);
 
     client.burn_from(&spender, &user1, &300_0000000);
@@ -202,3 +325,464 @@ fn test_burn_from() {
     assert_eq!(client.total_supply(), 700_0000000);
     assert_eq!(client.allowance(&user1, &spender), 200_0000000);
 }
+
+#[test]
+fn test_approve_negative_amount() {
+    let (env, client, _admin, _minter, user1) = setup_test();
+    let spender = Address::generate(&env);
+
+    assert_eq!(
+        client.try_approve(&user1, &spender, &-1, &1000),
+        Err(Ok(TokenError::NegativeAmount))
+    );
+}
+
+#[test]
+fn test_burn_from_insufficient_allowance() {
+    let (env, client, _admin, minter, user1) = setup_test();
+    let spender = Address::generate(&env);
+
+    client.mint(&minter, &user1, &1000_0000000);
+    client.approve(&user1, &spender, &100_0000000, &1000);
+
+    assert_eq!(
+        client.try_burn_from(&spender, &user1, &200_0000000),
+        Err(Ok(TokenError::InsufficientAllowance))
+    );
+}
+
+#[test]
+fn test_uninitialized_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(SxlmToken, ());
+    let client = SxlmTokenClient::new(&env, &contract_id);
+    let user = Address::generate(&env);
+
+    assert_eq!(client.try_admin(), Err(Ok(TokenError::NotInitialized)));
+    assert_eq!(client.try_mint(&user, &user, &1), Err(Ok(TokenError::NotInitialized)));
+}
+
+#[test]
+fn test_migrate_legacy_storage() {
+    use crate::DataKey;
+
+    let (env, client, _admin, minter, user) = setup_test();
+    assert_eq!(client.schema_version(), 6);
+    assert_eq!(client.try_migrate(&6), Err(Ok(TokenError::InvalidSchemaVersion)));
+    client.mint(&minter, &user, &100);
+    let hook = Address::generate(&env);
+
+    // Simulate a deployment from before schema versioning, which kept
+    // metadata under ad-hoc keys and had a single minter owning the
+    // transfer pause and hook
+    env.as_contract(&client.address, || {
+        let storage = env.storage().instance();
+        storage.remove(&DataKey::SchemaVersion);
+        storage.remove(&DataKey::Minters);
+        storage.remove(&DataKey::TransferPauses);
+        storage.remove(&DataKey::TransferHooks);
+        storage.remove(&soroban_sdk::symbol_short!("METADATA"));
+        storage.set(&DataKey::Name, &String::from_str(&env, "Staked XLM"));
+        storage.set(&DataKey::Symbol, &String::from_str(&env, "sXLM"));
+        storage.set(&DataKey::Decimals, &7u32);
+        storage.set(&DataKey::TransfersPaused, &true);
+        storage.set(&DataKey::TransferHook, &hook);
+    });
+    assert_eq!(client.try_mint(&minter, &user, &1), Err(Ok(TokenError::SchemaOutdated)));
+    assert_eq!(client.try_migrate(&5), Err(Ok(TokenError::InvalidSchemaVersion)));
+
+    client.migrate(&6);
+    assert_eq!(client.schema_version(), 6);
+    assert_eq!(client.name(), String::from_str(&env, "Staked XLM"));
+    assert_eq!(client.symbol(), String::from_str(&env, "sXLM"));
+    assert_eq!(client.decimals(), 7);
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().instance().has(&DataKey::Name));
+    });
+    // The existing supply is charged to the primary minter
+    let minters = client.minters();
+    assert_eq!(minters.len(), 1);
+    assert_eq!(minters.get(0).unwrap().minter, minter);
+    assert_eq!(minters.get(0).unwrap().outstanding, 100);
+    client.mint(&minter, &user, &1);
+    // ...and the transfer pause and hook are handed to it
+    assert_eq!(client.transfer_pauses(), soroban_sdk::vec![&env, minter.clone()]);
+    assert_eq!(client.transfer_hook(&minter), Some(hook));
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().instance().has(&DataKey::TransfersPaused));
+        assert!(!env.storage().instance().has(&DataKey::TransferHook));
+    });
+}
+
+#[test]
+fn test_transfers_paused() {
+    let (env, client, _admin, minter, user1) = setup_test();
+    let user2 = Address::generate(&env);
+    let spender = Address::generate(&env);
+
+    client.mint(&minter, &user1, &1000_0000000);
+    client.approve(&user1, &spender, &500_0000000, &1000);
+    client.set_transfers_paused(&minter, &true);
+    assert!(client.transfers_paused());
+
+    assert_eq!(
+        client.try_transfer(&user1, &user2, &1),
+        Err(Ok(TokenError::TransfersPaused))
+    );
+    assert_eq!(
+        client.try_transfer_from(&spender, &user1, &user2, &1),
+        Err(Ok(TokenError::TransfersPaused))
+    );
+
+    // The vault can still mint and burn
+    client.mint(&minter, &user2, &1);
+    client.minter_burn(&minter, &user1, &1);
+
+    client.set_transfers_paused(&minter, &false);
+    client.transfer(&user1, &user2, &1);
+}
+
+#[test]
+fn test_transfer_pauses_are_held_per_caller() {
+    let (env, client, admin, minter, user1) = setup_test();
+    let user2 = Address::generate(&env);
+    let bridge = Address::generate(&env);
+    client.add_minter(&bridge, &1000);
+    client.mint(&minter, &user1, &1000);
+
+    assert_eq!(
+        client.try_set_transfers_paused(&user1, &true),
+        Err(Ok(TokenError::Unauthorized))
+    );
+
+    // The primary lifting its pause leaves the bridge's and admin's in place
+    client.set_transfers_paused(&minter, &true);
+    client.set_transfers_paused(&bridge, &true);
+    client.set_transfers_paused(&admin, &true);
+    client.set_transfers_paused(&minter, &false);
+    assert_eq!(client.transfer_pauses(), soroban_sdk::vec![&env, bridge.clone(), admin.clone()]);
+    assert_eq!(
+        client.try_transfer(&user1, &user2, &1),
+        Err(Ok(TokenError::TransfersPaused))
+    );
+
+    client.set_transfers_paused(&admin, &false);
+    assert!(client.transfers_paused());
+
+    // Removing a minter lifts its pause
+    client.remove_minter(&bridge);
+    assert!(!client.transfers_paused());
+    client.transfer(&user1, &user2, &1);
+}
+
+#[test]
+fn test_transfer_hook() {
+    let (env, client, _admin, minter, user1) = setup_test();
+    let user2 = Address::generate(&env);
+    let spender = Address::generate(&env);
+    let hook_id = env.register(RecordingHook, ());
+    let hook = RecordingHookClient::new(&env, &hook_id);
+
+    client.mint(&minter, &user1, &1000_0000000);
+    client.transfer(&user1, &user2, &1);
+    assert_eq!(hook.last(), None);
+
+    let other = Address::generate(&env);
+    assert_eq!(
+        client.try_set_transfer_hook(&other, &Some(hook_id.clone())),
+        Err(Ok(TokenError::NotMinter))
+    );
+    client.set_transfer_hook(&minter, &Some(hook_id.clone()));
+    assert_eq!(client.transfer_hook(&minter), Some(hook_id));
+
+    client.transfer(&user1, &user2, &100);
+    assert_eq!(hook.last(), Some((user1.clone(), user2.clone(), 100)));
+
+    client.approve(&user1, &spender, &500, &1000);
+    client.transfer_from(&spender, &user1, &spender, &50);
+    assert_eq!(hook.last(), Some((user1.clone(), spender.clone(), 50)));
+
+    // Mints and burns are not transfers
+    client.mint(&minter, &user2, &7);
+    client.minter_burn(&minter, &user2, &7);
+    assert_eq!(hook.last(), Some((user1, spender, 50)));
+
+    client.set_transfer_hook(&minter, &None);
+    assert_eq!(client.transfer_hook(&minter), None);
+}
+
+#[test]
+fn test_every_minter_hook_is_notified() {
+    let (env, client, _admin, minter, user1) = setup_test();
+    let user2 = Address::generate(&env);
+    let bridge = Address::generate(&env);
+    let primary_hook = RecordingHookClient::new(&env, &env.register(RecordingHook, ()));
+    let bridge_hook = RecordingHookClient::new(&env, &env.register(RecordingHook, ()));
+    client.add_minter(&bridge, &1000);
+    client.set_transfer_hook(&minter, &Some(primary_hook.address.clone()));
+    client.set_transfer_hook(&bridge, &Some(bridge_hook.address.clone()));
+
+    // sXLM from either minter is fungible, so both hooks see every transfer
+    client.mint(&bridge, &user1, &100);
+    client.transfer(&user1, &user2, &40);
+    let moved = Some((user1.clone(), user2.clone(), 40));
+    assert_eq!(primary_hook.last(), moved);
+    assert_eq!(bridge_hook.last(), moved);
+
+    // Removing a minter drops its hook
+    client.remove_minter(&bridge);
+    assert_eq!(client.transfer_hook(&bridge), None);
+    client.transfer(&user1, &user2, &10);
+    assert_eq!(bridge_hook.last(), moved);
+    assert_eq!(primary_hook.last(), Some((user1, user2, 10)));
+}
+
+#[test]
+fn test_allowance_expires() {
+    let (env, client, _admin, minter, user1) = setup_test();
+    let user2 = Address::generate(&env);
+    let spender = Address::generate(&env);
+    client.mint(&minter, &user1, &1000_0000000);
+    env.ledger().with_mut(|l| l.sequence_number = 100);
+
+    // Approving a non-zero amount that is already expired is rejected
+    assert_eq!(
+        client.try_approve(&user1, &spender, &500, &99),
+        Err(Ok(TokenError::InvalidExpiration))
+    );
+    // Clearing an allowance works with any expiration
+    client.approve(&user1, &spender, &0, &0);
+
+    client.approve(&user1, &spender, &500, &200);
+    client.transfer_from(&spender, &user1, &user2, &100);
+    assert_eq!(client.allowance(&user1, &spender), 400);
+
+    // Usable through the expiration ledger itself
+    env.ledger().with_mut(|l| l.sequence_number = 200);
+    client.burn_from(&spender, &user1, &100);
+    assert_eq!(client.allowance(&user1, &spender), 300);
+
+    env.ledger().with_mut(|l| l.sequence_number = 201);
+    assert_eq!(client.allowance(&user1, &spender), 0);
+    assert_eq!(
+        client.try_transfer_from(&spender, &user1, &user2, &1),
+        Err(Ok(TokenError::InsufficientAllowance))
+    );
+    assert_eq!(
+        client.try_burn_from(&spender, &user1, &1),
+        Err(Ok(TokenError::InsufficientAllowance))
+    );
+}
+
+#[test]
+fn test_freeze_blocks_holder_moves() {
+    let (env, client, admin, minter, user1) = setup_test();
+    let user2 = Address::generate(&env);
+    let spender = Address::generate(&env);
+    let compliance = Address::generate(&env);
+    client.mint(&minter, &user1, &1000_0000000);
+    client.approve(&user1, &spender, &500_0000000, &1000);
+
+    // Only the admin or the compliance officer can freeze
+    assert_eq!(
+        client.try_freeze(&compliance, &user1),
+        Err(Ok(TokenError::Unauthorized))
+    );
+    client.set_compliance(&Some(compliance.clone()));
+    assert_eq!(client.compliance(), Some(compliance.clone()));
+    client.freeze(&compliance, &user1);
+    assert!(client.is_frozen(&user1));
+
+    assert_eq!(
+        client.try_transfer(&user1, &user2, &1),
+        Err(Ok(TokenError::AccountFrozen))
+    );
+    assert_eq!(
+        client.try_transfer_from(&spender, &user1, &user2, &1),
+        Err(Ok(TokenError::AccountFrozen))
+    );
+    assert_eq!(
+        client.try_burn_self(&user1, &1),
+        Err(Ok(TokenError::AccountFrozen))
+    );
+    assert_eq!(
+        client.try_burn_from(&spender, &user1, &1),
+        Err(Ok(TokenError::AccountFrozen))
+    );
+
+    // A frozen holder can still receive
+    client.mint(&minter, &user2, &10);
+    client.transfer(&user2, &user1, &10);
+
+    client.unfreeze(&admin, &user1);
+    assert!(!client.is_frozen(&user1));
+    client.transfer(&user1, &user2, &1);
+
+    client.set_compliance(&None);
+    assert_eq!(
+        client.try_freeze(&compliance, &user1),
+        Err(Ok(TokenError::Unauthorized))
+    );
+}
+
+#[test]
+fn test_capped_minters() {
+    let (env, client, admin, minter, user) = setup_test();
+    let bridge = Address::generate(&env);
+
+    client.add_minter(&bridge, &1000);
+    assert_eq!(client.try_add_minter(&bridge, &1000), Err(Ok(TokenError::MinterExists)));
+    assert_eq!(client.minters().len(), 2);
+    assert_eq!(client.minter_headroom(&bridge), 1000);
+    assert_eq!(client.minter_headroom(&admin), 0);
+    assert_eq!(client.try_mint(&admin, &user, &1), Err(Ok(TokenError::NotMinter)));
+
+    client.mint(&bridge, &user, &600);
+    assert_eq!(client.minter_headroom(&bridge), 400);
+    assert_eq!(
+        client.try_mint(&bridge, &user, &401),
+        Err(Ok(TokenError::MinterCapExceeded))
+    );
+
+    // Burning frees headroom, but only for sXLM the minter itself issued
+    client.mint(&minter, &user, &500);
+    assert_eq!(client.issued_balance(&user, &bridge), 600);
+    assert_eq!(client.issued_balance(&user, &minter), 500);
+    assert_eq!(
+        client.try_minter_burn(&bridge, &user, &601),
+        Err(Ok(TokenError::ExceedsIssued))
+    );
+    client.minter_burn(&bridge, &user, &500);
+    assert_eq!(client.minter_headroom(&bridge), 900);
+    assert_eq!(client.total_supply(), 600);
+
+    // Burns through an operator's allowance free headroom the same way
+    let operator = Address::generate(&env);
+    client.approve(&user, &operator, &100, &1000);
+    client.minter_burn_from(&bridge, &operator, &user, &100);
+    assert_eq!(client.minter_headroom(&bridge), 1000);
+    assert_eq!(client.allowance(&user, &operator), 0);
+
+    // Holder burns come off the issuing minters' supply, primary's first
+    client.mint(&bridge, &user, &100);
+    assert_eq!(client.minter_outstanding(&minter), 500);
+    client.burn_self(&user, &550);
+    assert_eq!(client.minter_outstanding(&minter), 0);
+    assert_eq!(client.minter_outstanding(&bridge), 50);
+    assert_eq!(client.issued_balance(&user, &bridge), 50);
+
+    // Lowering the cap below what is outstanding only stops new mints
+    client.set_minter_cap(&bridge, &50);
+    assert_eq!(client.minter_headroom(&bridge), 0);
+    assert_eq!(
+        client.try_mint(&bridge, &user, &1),
+        Err(Ok(TokenError::MinterCapExceeded))
+    );
+
+    assert_eq!(client.try_remove_minter(&minter), Err(Ok(TokenError::PrimaryMinter)));
+    client.remove_minter(&bridge);
+    assert_eq!(client.try_remove_minter(&bridge), Err(Ok(TokenError::NotMinter)));
+    assert_eq!(client.try_mint(&bridge, &user, &1), Err(Ok(TokenError::NotMinter)));
+    assert_eq!(client.minters().len(), 1);
+}
+
+#[test]
+fn test_issued_sxlm_follows_transfers() {
+    let (env, client, _admin, minter, user1) = setup_test();
+    let user2 = Address::generate(&env);
+    let bridge = Address::generate(&env);
+    client.add_minter(&bridge, &1000);
+
+    client.mint(&minter, &user1, &300);
+    client.mint(&bridge, &user1, &200);
+
+    // The primary minter's sXLM moves first, then other minters'
+    client.transfer(&user1, &user2, &400);
+    assert_eq!(client.issued_balance(&user1, &minter), 0);
+    assert_eq!(client.issued_balance(&user1, &bridge), 100);
+    assert_eq!(client.issued_balance(&user2, &minter), 300);
+    assert_eq!(client.issued_balance(&user2, &bridge), 100);
+
+    // Neither minter can burn the other's sXLM
+    assert_eq!(
+        client.try_minter_burn(&minter, &user1, &1),
+        Err(Ok(TokenError::ExceedsIssued))
+    );
+    assert_eq!(
+        client.try_minter_burn(&bridge, &user2, &101),
+        Err(Ok(TokenError::ExceedsIssued))
+    );
+    client.minter_burn(&bridge, &user2, &100);
+    assert_eq!(client.issued_balance(&user2, &bridge), 0);
+    assert_eq!(client.issued_balance(&user2, &minter), 300);
+}
+
+#[test]
+fn test_migration_charges_unattributed_supply_to_primary() {
+    use crate::DataKey;
+
+    let (env, client, _admin, minter, user) = setup_test();
+    let bridge = Address::generate(&env);
+    client.add_minter(&bridge, &1000);
+    client.mint(&minter, &user, &300);
+
+    // A v5 deployment whose bridge minted without recording the holder
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&DataKey::SchemaVersion, &5u32);
+        env.storage().instance().set(&DataKey::TotalSupply, &500i128);
+        let mut minters: soroban_sdk::Map<Address, crate::MinterAllowance> =
+            env.storage().instance().get(&DataKey::Minters).unwrap();
+        let mut allowance = minters.get(bridge.clone()).unwrap();
+        allowance.outstanding = 200;
+        minters.set(bridge.clone(), allowance);
+        env.storage().instance().set(&DataKey::Minters, &minters);
+        env.storage().persistent().set(&DataKey::Balance(user.clone()), &500i128);
+    });
+
+    client.migrate(&6);
+    assert_eq!(client.minter_outstanding(&minter), 500);
+    assert_eq!(client.minter_outstanding(&bridge), 0);
+    assert_eq!(client.issued_balance(&user, &minter), 500);
+    client.minter_burn(&minter, &user, &500);
+    assert_eq!(client.total_supply(), 0);
+}
+
+#[test]
+fn test_migration_revokes_legacy_allowances() {
+    use crate::DataKey;
+
+    let (env, client, _admin, minter, user1) = setup_test();
+    let user2 = Address::generate(&env);
+    let spender = Address::generate(&env);
+    client.mint(&minter, &user1, &1000);
+
+    // A v1 deployment with an approval stored as a bare amount in
+    // persistent storage, without an expiration
+    let legacy_key = DataKey::Allowance(user1.clone(), spender.clone());
+    env.as_contract(&client.address, || {
+        let storage = env.storage().instance();
+        storage.set(&DataKey::SchemaVersion, &1u32);
+        storage.remove(&DataKey::Minters);
+        storage.remove(&soroban_sdk::symbol_short!("METADATA"));
+        storage.set(&DataKey::Name, &String::from_str(&env, "Staked XLM"));
+        storage.set(&DataKey::Symbol, &String::from_str(&env, "sXLM"));
+        env.storage().persistent().set(&legacy_key, &500i128);
+    });
+
+    // Upgrading revokes the approval
+    client.migrate(&6);
+    assert_eq!(client.allowance(&user1, &spender), 0);
+    assert_eq!(
+        client.try_transfer_from(&spender, &user1, &user2, &1),
+        Err(Ok(TokenError::InsufficientAllowance))
+    );
+
+    // Re-approving replaces it and clears the stale entry
+    client.approve(&user1, &spender, &200, &1000);
+    assert_eq!(client.allowance(&user1, &spender), 200);
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().persistent().has(&legacy_key));
+    });
+}
@@ -1,17 +1,64 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, Env, IntoVal, Symbol,
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contracterror, contractimpl, contracttype, token, vec, Address, BytesN, Env,
+    IntoVal, Symbol, Vec,
 };
 
+mod math;
+pub mod yield_strategy;
+
+use math::{mul_div, Rounding};
+use yield_strategy::StrategyClient;
+
 // Precision for exchange rate calculations (7 decimals)
 const PRECISION: i128 = 1_0000000;
 
+// Virtual offsets added to total assets and supply in every share conversion.
+// Keeps the initial rate at 1:1 while making a donation-based exchange rate
+// inflation cost the attacker ~1000x what it can round away from depositors.
+const VIRTUAL_ASSETS: i128 = 1_000;
+const VIRTUAL_SHARES: i128 = 1_000;
+
 // Minimum deposit/withdraw amounts (0.1 XLM)
 const MIN_AMOUNT: i128 = 1000000;
 
 // Maximum single deposit (1M XLM) - can be adjusted by admin
-const DEFAULT_MAX_DEPOSIT: i128 = 1_000_000_0000000;
+const DEFAULT_MAX_DEPOSIT: i128 = 1_000_000 * PRECISION;
+
+// Basis points denominator for allocation weights
+const BPS_DENOMINATOR: u32 = 10_000;
+
+// Upper bound on registered strategies (keeps TVL queries bounded)
+const MAX_STRATEGIES: u32 = 10;
+
+// Fee bounds (basis points)
+const MAX_PERFORMANCE_FEE_BPS: u32 = 3_000; // 30% of realized yield
+const MAX_MANAGEMENT_FEE_BPS: u32 = 500; // 5% of TVL per year
+
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+// Yield added via add_yield unlocks linearly over this many ledgers
+const DEFAULT_VESTING_PERIOD: u32 = DAY_IN_LEDGERS;
+const MAX_VESTING_PERIOD: u32 = 7 * DAY_IN_LEDGERS;
+
+// Delay (seconds) between scheduling a timelocked action and executing it
+const DEFAULT_TIMELOCK_DELAY: u64 = 2 * 24 * 60 * 60; // 2 days
+const MAX_TIMELOCK_DELAY: u64 = 30 * 24 * 60 * 60; // 30 days
+
+// Operations paused together by `pause` / `unpause`. sXLM transfers are left
+// alone so holders can still move their position during an incident.
+const VAULT_OPERATIONS: [Operation; 4] = [
+    Operation::Deposit,
+    Operation::Withdraw,
+    Operation::Yield,
+    Operation::Strategy,
+];
+
+// Storage layout version this code expects. Bump it together with a new
+// step in `migrate_step` whenever an upgrade changes how storage is laid out.
+const SCHEMA_VERSION: u32 = 2;
 
 // Storage TTL constants
 const DAY_IN_LEDGERS: u32 = 17280;
@@ -29,11 +76,283 @@ pub enum DataKey {
     TotalDeposits,
     YieldAccrued,
     Initialized,
-    Paused,
+    Paused,          // Legacy global pause flag, split into PauseFlag in schema v2
     MaxDeposit,
     TotalCap,        // Maximum total TVL
-    UserDeposit(Address), // Track per-user deposits
+    UserDeposit(Address), // Legacy deposit counter, superseded by Position
     Reentrancy,      // Reentrancy guard
+    WithdrawTicket(u64),  // Queued withdrawal by ticket id
+    QueueHead,       // Oldest ticket not yet finalized
+    QueueTail,       // Next ticket id to issue
+    PendingWithdrawals,   // XLM owed to all unclaimed tickets
+    FinalizedWithdrawals, // XLM reserved for finalized, unclaimed tickets
+    Strategies,      // Registered strategy addresses
+    Strategy(Address),    // Per-strategy config
+    Role(Role, Address),  // Role membership (Owner is always the Admin)
+    PendingAdmin,    // Proposed admin awaiting acceptance
+    TimelockDelay,   // Seconds a scheduled action must wait
+    TimelockNextId,  // Next timelock operation id
+    TimelockOps,     // Ids of scheduled, not yet executed operations
+    TimelockOp(u64), // Scheduled operation by id
+    SchemaVersion,   // Storage layout version (absent before versioning)
+    PauseFlag(Operation), // Per-operation pause flag
+    WithdrawOnly,    // Emergency mode: only pro-rata redemptions
+    Shutdown,        // Terminal shutdown snapshot (absent while operating)
+    StrategyLoss(Address), // XLM a strategy failed to return at shutdown
+    DefaultUserCap,  // Per-address cap on position value, unless overridden
+    UserCap(Address),     // Per-address cap override
+    AllowlistEnabled,     // Only allowlisted addresses may receive deposits
+    Allowlisted(Address), // Allowlist membership
+    OutflowLimit,    // Rolling-window cap on instant withdrawals
+    OutflowUsed,     // Instant outflow counted against the limit as of OutflowUpdated
+    OutflowUpdated,  // Ledger OutflowUsed was last updated
+    Position(Address),    // Per-holder cost basis and realized PnL
+    IdleBufferBps,   // Share of TVL kept idle for instant withdrawals
+    Treasury,        // Receives fee shares
+    PerformanceFeeBps,    // Cut of each add_yield
+    ManagementFeeBps,     // Annualized cut of TVL
+    LastFeeAccrual,  // Ledger timestamp of last management fee accrual
+    VestingPeriod,   // Ledgers over which added yield unlocks
+    LockedYield,     // Yield still locked as of VestingStart
+    VestingStart,    // Ledger the current vesting schedule started
+    VestingEnd,      // Ledger the current vesting schedule ends
+}
+
+/// Stable error codes returned by every vault entrypoint.
+/// Codes are part of the public interface: never renumber, only append.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum VaultError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    Paused = 3,
+    BelowMinimum = 4,
+    AboveMaxDeposit = 5,
+    CapExceeded = 6,
+    InsufficientLiquidity = 7,
+    Slippage = 8,
+    Reentrant = 9,
+    InsufficientBalance = 10,
+    DepositTooSmall = 11,
+    WithdrawalTooSmall = 12,
+    InvalidAmount = 13,
+    Overflow = 14,
+    TicketNotFound = 15,
+    NotTicketOwner = 16,
+    TicketNotFinalized = 17,
+    StrategyExists = 18,
+    StrategyNotFound = 19,
+    InvalidAllocation = 20,
+    TooManyStrategies = 21,
+    StrategyNotEmpty = 22,
+    Unauthorized = 23,
+    DebtLimitExceeded = 24,
+    FeeTooHigh = 25,
+    TreasuryNotSet = 26,
+    InvalidVestingPeriod = 27,
+    InsufficientAllowance = 28,
+    InvalidRole = 29,
+    NoPendingTransfer = 30,
+    TransferExpired = 31,
+    OperationNotFound = 32,
+    TimelockNotReady = 33,
+    InvalidDelay = 34,
+    SchemaOutdated = 35,
+    InvalidSchemaVersion = 36,
+    VaultShutdown = 37,
+    NotShutdown = 38,
+    UserCapExceeded = 39,
+    NotAllowlisted = 40,
+    InvalidOutflowLimit = 41,
+    AccountFrozen = 42,
+    PositionNotSynced = 43, // Legacy depositor must call `sync_position` before transferring
+}
+
+/// Privileged roles. The owner (`Admin`) implicitly holds every role and is
+/// the only one who can grant or revoke them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Role {
+    Owner,      // Upgrades, admin transfer, role management
+    Guardian,   // Pause / unpause
+    Keeper,     // add_yield, harvest, rebalance
+    FeeManager, // Treasury and fee rates
+    Strategist, // Strategy registry, allocations, deposit and outflow limits
+}
+
+/// A queued withdrawal. The sXLM is burned when the ticket is issued, so
+/// `xlm_amount` is fixed at the exchange rate of the request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WithdrawTicket {
+    pub owner: Address,
+    pub sxlm_amount: i128,
+    pub xlm_amount: i128,
+    pub requested_ledger: u32,
+    pub finalized: bool,
+}
+
+/// Registry entry for a yield strategy
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StrategyConfig {
+    pub weight_bps: u32,  // Target share of TVL in basis points
+    pub debt_limit: i128, // Maximum XLM the vault may deploy to it
+}
+
+/// Fee settings plus the management fee accrued since the last settlement
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FeePreview {
+    pub treasury: Option<Address>,
+    pub performance_fee_bps: u32,
+    pub management_fee_bps: u32,
+    pub pending_management_fee: i128, // XLM value not yet minted to treasury
+    pub last_accrual: u64,
+}
+
+/// A proposed authority change that takes effect once `address` accepts it
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PendingTransfer {
+    pub address: Address,
+    pub expires_ledger: Option<u32>, // Last ledger the proposal can be accepted
+}
+
+/// Vault operations that can be paused independently
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Operation {
+    Deposit,  // deposit, deposit_for, mint
+    Withdraw, // withdraw, redeem, withdraw_assets, request_withdraw
+    Yield,    // add_yield, harvest
+    Strategy, // Moving XLM to and from strategies, rebalance
+    Transfer, // sXLM holder-to-holder transfers (enforced by the token)
+}
+
+/// All pause flags, as returned by `pause_status`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PauseStatus {
+    pub deposits: bool,
+    pub withdrawals: bool,
+    pub yield_additions: bool,
+    pub strategies: bool,
+    pub transfers: bool,
+    pub withdraw_only: bool, // Implies deposits, yield and strategy deployment are paused
+    pub shutdown: bool,      // Terminal: only emergency_redeem and queued claims remain
+}
+
+/// Cap on instant withdrawals over a rolling window of ledgers. Outflow
+/// counted against the limit decays linearly over `window_ledgers`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OutflowLimit {
+    pub window_ledgers: u32,
+    pub max_amount: Option<i128>, // Absolute XLM per window
+    pub max_bps: Option<u32>,     // Share of total assets per window
+}
+
+/// Cost basis bookkeeping for one holder. `shares` is the sXLM the vault has
+/// seen the holder acquire (by deposit, fee mint or transfer) and `cost_basis`
+/// the XLM paid for them, so `cost_basis / shares` is the weighted average
+/// cost per sXLM.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct PositionRecord {
+    pub shares: i128,
+    pub cost_basis: i128,
+    pub realized_pnl: i128, // Proceeds minus cost of every sXLM redeemed
+}
+
+/// A holder's position as returned by `position`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Position {
+    pub shares: i128,
+    pub value: i128,          // XLM the shares convert to at the current rate
+    pub cost_basis: i128,     // XLM paid for the shares
+    pub avg_cost: i128,       // XLM per sXLM, scaled by PRECISION
+    pub unrealized_pnl: i128, // value - cost_basis
+    pub realized_pnl: i128,
+}
+
+/// One registered strategy as reported by `vault_state`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StrategyState {
+    pub strategy: Address,
+    pub weight_bps: u32,
+    pub debt_limit: i128,
+    pub deployed: i128, // XLM value the strategy reports
+}
+
+/// Everything a dashboard or monitor needs in one call, as returned by
+/// `vault_state`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct VaultState {
+    pub total_assets: i128,
+    pub idle_assets: i128, // Excludes XLM owed to queued withdrawals
+    pub strategies: Vec<StrategyState>,
+    pub total_supply: i128, // sXLM issued by this vault
+    pub exchange_rate: i128,
+    pub locked_yield: i128,
+    pub pause: PauseStatus,
+    pub max_deposit_per_tx: i128,
+    pub total_cap: i128,
+    pub total_deposits: i128,
+    pub default_user_cap: i128,
+    pub fees: FeePreview,
+    pub pending_withdrawals: i128,
+    pub withdraw_capacity: i128,
+    pub schema_version: u32,
+}
+
+/// A holder's position and limits, as returned by `user_state`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UserState {
+    pub position: Position,
+    pub xlm_balance: i128,
+    pub max_deposit: i128,
+    pub max_withdraw: i128,
+    pub deposit_limit: i128,
+    pub allowlisted: bool,
+}
+
+/// Frozen redemption rate recorded by `emergency_shutdown`. Each share
+/// redeems for `assets / supply` XLM from then on.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ShutdownState {
+    pub assets: i128,    // XLM recovered for holders, excluding queued withdrawals
+    pub supply: i128,    // sXLM issued by this vault at shutdown
+    pub total_loss: i128, // XLM strategies reported but did not return
+    pub ledger: u32,
+}
+
+/// Sensitive changes that must wait out the timelock delay
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum TimelockAction {
+    Upgrade(BytesN<32>),
+    SetTotalCap(i128),
+    SetMaxDeposit(i128),
+    ProposeAdmin(Address, Option<u32>),
+    SetPerformanceFee(u32),
+    SetManagementFee(u32),
+    SetTimelockDelay(u64),
+}
+
+/// A scheduled timelock action, executable from `eta` (ledger timestamp)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TimelockOp {
+    pub action: TimelockAction,
+    pub proposer: Address,
+    pub eta: u64,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -59,12 +378,25 @@ fn extend_persistent_ttl(env: &Env, key: &DataKey) {
     }
 }
 
-fn get_xlm_token(env: &Env) -> Address {
-    env.storage().instance().get(&DataKey::XlmToken).unwrap()
+fn get_admin(env: &Env) -> Result<Address, VaultError> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .ok_or(VaultError::NotInitialized)
+}
+
+fn get_xlm_token(env: &Env) -> Result<Address, VaultError> {
+    env.storage()
+        .instance()
+        .get(&DataKey::XlmToken)
+        .ok_or(VaultError::NotInitialized)
 }
 
-fn get_sxlm_token(env: &Env) -> Address {
-    env.storage().instance().get(&DataKey::SxlmToken).unwrap()
+fn get_sxlm_token(env: &Env) -> Result<Address, VaultError> {
+    env.storage()
+        .instance()
+        .get(&DataKey::SxlmToken)
+        .ok_or(VaultError::NotInitialized)
 }
 
 fn get_total_deposits(env: &Env) -> i128 {
@@ -89,498 +421,2925 @@ fn set_yield_accrued(env: &Env, amount: i128) {
     env.storage().instance().set(&DataKey::YieldAccrued, &amount);
 }
 
-fn is_paused(env: &Env) -> bool {
+/// The pause flag for `operation` as set by the guardian
+fn get_pause_flag(env: &Env, operation: Operation) -> bool {
     env.storage()
         .instance()
-        .get(&DataKey::Paused)
+        .get(&DataKey::PauseFlag(operation))
         .unwrap_or(false)
 }
 
-fn get_max_deposit(env: &Env) -> i128 {
+fn is_withdraw_only(env: &Env) -> bool {
     env.storage()
         .instance()
-        .get(&DataKey::MaxDeposit)
-        .unwrap_or(DEFAULT_MAX_DEPOSIT)
+        .get(&DataKey::WithdrawOnly)
+        .unwrap_or(false)
 }
 
-fn get_total_cap(env: &Env) -> i128 {
-    env.storage()
-        .instance()
-        .get(&DataKey::TotalCap)
-        .unwrap_or(i128::MAX)
+fn get_shutdown(env: &Env) -> Option<ShutdownState> {
+    env.storage().instance().get(&DataKey::Shutdown)
 }
 
-fn get_user_deposit(env: &Env, user: &Address) -> i128 {
-    let key = DataKey::UserDeposit(user.clone());
-    let amount = env.storage().persistent().get(&key).unwrap_or(0);
-    if amount > 0 {
-        extend_persistent_ttl(env, &key);
-    }
-    amount
+fn is_shutdown(env: &Env) -> bool {
+    env.storage().instance().has(&DataKey::Shutdown)
 }
 
-fn set_user_deposit(env: &Env, user: &Address, amount: i128) {
-    let key = DataKey::UserDeposit(user.clone());
-    env.storage().persistent().set(&key, &amount);
-    extend_persistent_ttl(env, &key);
+/// Whether `operation` is blocked, either by its own flag, by shutdown, or by
+/// withdraw-only mode (which stops everything that brings XLM in or sends it
+/// to strategies)
+fn is_paused(env: &Env, operation: Operation) -> bool {
+    if is_shutdown(env) {
+        return true;
+    }
+    let blocked_by_mode = is_withdraw_only(env)
+        && matches!(
+            operation,
+            Operation::Deposit | Operation::Yield | Operation::Strategy
+        );
+    blocked_by_mode || get_pause_flag(env, operation)
 }
 
-// ============ Reentrancy Guard ============
-
-fn require_not_reentering(env: &Env) {
-    let state: ReentrancyState = env
-        .storage()
-        .instance()
-        .get(&DataKey::Reentrancy)
-        .unwrap_or(ReentrancyState::Unlocked);
+fn require_not_paused(env: &Env, operation: Operation) -> Result<(), VaultError> {
+    if is_shutdown(env) {
+        return Err(VaultError::VaultShutdown);
+    }
+    if is_paused(env, operation) {
+        return Err(VaultError::Paused);
+    }
+    Ok(())
+}
 
-    if state == ReentrancyState::Locked {
-        panic!("Reentrant call detected");
+fn set_pause_flag(env: &Env, operation: Operation, paused: bool) -> Result<(), VaultError> {
+    if operation == Operation::Transfer {
+        // The vault is an sXLM minter, which holds its own transfer pause.
+        // Transfers resume once every minter and the admin have lifted theirs.
+        env.invoke_contract::<()>(
+            &get_sxlm_token(env)?,
+            &Symbol::new(env, "set_transfers_paused"),
+            (env.current_contract_address(), paused).into_val(env),
+        );
+    } else {
+        env.storage()
+            .instance()
+            .set(&DataKey::PauseFlag(operation), &paused);
     }
+
+    env.events().publish(
+        (Symbol::new(env, "pause_set"), operation),
+        paused,
+    );
+    Ok(())
 }
 
-fn lock_reentrancy(env: &Env) {
+fn get_max_deposit(env: &Env) -> i128 {
     env.storage()
         .instance()
-        .set(&DataKey::Reentrancy, &ReentrancyState::Locked);
+        .get(&DataKey::MaxDeposit)
+        .unwrap_or(DEFAULT_MAX_DEPOSIT)
 }
 
-fn unlock_reentrancy(env: &Env) {
+fn get_total_cap(env: &Env) -> i128 {
     env.storage()
         .instance()
-        .set(&DataKey::Reentrancy, &ReentrancyState::Unlocked);
+        .get(&DataKey::TotalCap)
+        .unwrap_or(i128::MAX)
 }
 
-#[contract]
-pub struct Vault;
+// ============ Per-Address Limits ============
 
-#[contractimpl]
-impl Vault {
-    /// Initialize the vault
-    pub fn initialize(
-        env: Env,
-        admin: Address,
-        xlm_token: Address,
-        sxlm_token: Address,
-    ) {
-        if env.storage().instance().has(&DataKey::Initialized) {
-            panic!("Already initialized");
-        }
+fn get_default_user_cap(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::DefaultUserCap)
+        .unwrap_or(i128::MAX)
+}
 
-        env.storage().instance().set(&DataKey::Admin, &admin);
-        env.storage().instance().set(&DataKey::XlmToken, &xlm_token);
-        env.storage().instance().set(&DataKey::SxlmToken, &sxlm_token);
-        env.storage().instance().set(&DataKey::Initialized, &true);
-        env.storage().instance().set(&DataKey::Paused, &false);
-        env.storage().instance().set(&DataKey::MaxDeposit, &DEFAULT_MAX_DEPOSIT);
-        env.storage().instance().set(&DataKey::Reentrancy, &ReentrancyState::Unlocked);
-        set_total_deposits(&env, 0);
-        set_yield_accrued(&env, 0);
-        extend_instance_ttl(&env);
+/// The cap on `user`'s position value: their override, or the default
+fn get_user_cap(env: &Env, user: &Address) -> i128 {
+    let key = DataKey::UserCap(user.clone());
+    match env.storage().persistent().get(&key) {
+        Some(cap) => {
+            extend_persistent_ttl(env, &key);
+            cap
+        }
+        None => get_default_user_cap(env),
     }
+}
 
-    /// Deposit XLM and receive sXLM
-    /// Returns the amount of sXLM minted
-    pub fn deposit(env: Env, user: Address, amount: i128) -> i128 {
-        extend_instance_ttl(&env);
-        require_not_reentering(&env);
-        lock_reentrancy(&env);
+fn is_allowlist_enabled(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get(&DataKey::AllowlistEnabled)
+        .unwrap_or(false)
+}
 
-        // Validations
-        user.require_auth();
+fn is_allowlisted(env: &Env, user: &Address) -> bool {
+    let key = DataKey::Allowlisted(user.clone());
+    let listed = env.storage().persistent().has(&key);
+    if listed {
+        extend_persistent_ttl(env, &key);
+    }
+    listed
+}
 
-        if is_paused(&env) {
-            unlock_reentrancy(&env);
-            panic!("Vault is paused");
-        }
+/// XLM `user` may still add to their position under the allowlist and their
+/// cap, valuing the sXLM they already hold at the current rate
+fn deposit_headroom(env: &Env, user: &Address) -> Result<i128, VaultError> {
+    if is_allowlist_enabled(env) && !is_allowlisted(env, user) {
+        return Ok(0);
+    }
+    let cap = get_user_cap(env, user);
+    if cap == i128::MAX {
+        return Ok(cap);
+    }
+    let value = convert_to_assets(env, get_redeemable_shares(env, user)?, Rounding::Floor)?;
+    Ok(cap.saturating_sub(value).max(0))
+}
 
-        if amount < MIN_AMOUNT {
-            unlock_reentrancy(&env);
-            panic!("Amount below minimum (0.1 XLM)");
-        }
+// ============ Cost Basis ============
 
-        if amount > get_max_deposit(&env) {
-            unlock_reentrancy(&env);
-            panic!("Amount exceeds maximum single deposit");
-        }
+fn get_legacy_deposit(env: &Env, user: &Address) -> Option<i128> {
+    let key = DataKey::UserDeposit(user.clone());
+    let deposit = env.storage().persistent().get(&key);
+    if deposit.is_some() {
+        extend_persistent_ttl(env, &key);
+    }
+    deposit
+}
 
-        let total_after = get_total_deposits(&env).checked_add(amount).expect("Overflow");
-        if total_after > get_total_cap(&env) {
-            unlock_reentrancy(&env);
-            panic!("Deposit would exceed total cap");
-        }
+/// Position of a holder from before cost tracking, whose legacy deposit
+/// counter (XLM deposited less XLM withdrawn) is the cost of the `held` sXLM
+fn legacy_position(deposit: i128, held: i128) -> PositionRecord {
+    PositionRecord {
+        shares: held,
+        cost_basis: deposit.max(0),
+        realized_pnl: 0,
+    }
+}
+
+/// `user`'s position. Legacy holders are read from their deposit counter and
+/// current balance, so callers read it before changing the balance.
+fn get_position(env: &Env, user: &Address) -> Result<PositionRecord, VaultError> {
+    let key = DataKey::Position(user.clone());
+    if let Some(record) = env.storage().persistent().get(&key) {
+        extend_persistent_ttl(env, &key);
+        return Ok(record);
+    }
+    match get_legacy_deposit(env, user) {
+        Some(deposit) => Ok(legacy_position(deposit, get_sxlm_balance(env, user)?)),
+        None => Ok(PositionRecord::default()),
+    }
+}
 
-        let xlm_token = get_xlm_token(&env);
-        let sxlm_token = get_sxlm_token(&env);
+/// Store `user`'s position, retiring any legacy deposit counter it replaces
+fn set_position(env: &Env, user: &Address, record: &PositionRecord) {
+    let key = DataKey::Position(user.clone());
+    env.storage().persistent().set(&key, record);
+    extend_persistent_ttl(env, &key);
 
-        // Calculate sXLM to mint based on exchange rate
-        let exchange_rate = Self::get_exchange_rate(env.clone());
-        let sxlm_to_mint = amount
-            .checked_mul(PRECISION)
-            .expect("Overflow")
-            .checked_div(exchange_rate)
-            .expect("Division error");
+    let legacy_key = DataKey::UserDeposit(user.clone());
+    if env.storage().persistent().has(&legacy_key) {
+        env.storage().persistent().remove(&legacy_key);
+    }
+}
 
-        if sxlm_to_mint == 0 {
-            unlock_reentrancy(&env);
-            panic!("Deposit too small");
-        }
+/// Whether `user` still has only a legacy deposit counter
+fn is_unsynced_legacy_holder(env: &Env, user: &Address) -> bool {
+    !env.storage().persistent().has(&DataKey::Position(user.clone()))
+        && env.storage().persistent().has(&DataKey::UserDeposit(user.clone()))
+}
 
-        // Transfer XLM from user to vault
-        let xlm_client = token::Client::new(&env, &xlm_token);
-        xlm_client.transfer(&user, &env.current_contract_address(), &amount);
+/// Credit `user` with `shares` sXLM acquired for `cost` XLM
+fn add_to_position(env: &Env, user: &Address, shares: i128, cost: i128) -> Result<(), VaultError> {
+    let mut record = get_position(env, user)?;
+    record.shares = record.shares.checked_add(shares).ok_or(VaultError::Overflow)?;
+    record.cost_basis = record.cost_basis.checked_add(cost).ok_or(VaultError::Overflow)?;
+    set_position(env, user, &record);
+    Ok(())
+}
 
-        // Update total deposits
-        set_total_deposits(&env, total_after);
+/// Take `shares` sXLM out of `user`'s position at their average cost and
+/// return that cost. Shares the vault never saw `user` acquire (e.g. minted
+/// before cost tracking) carry no cost.
+fn remove_from_position(env: &Env, user: &Address, shares: i128) -> Result<i128, VaultError> {
+    let mut record = get_position(env, user)?;
+    if record.shares <= 0 {
+        return Ok(0);
+    }
+    let tracked = shares.min(record.shares);
+    let cost = mul_div(record.cost_basis, tracked, record.shares, Rounding::Floor)?;
+    record.shares -= tracked;
+    record.cost_basis -= cost;
+    set_position(env, user, &record);
+    Ok(cost)
+}
 
-        // Track user deposit
-        let user_total = get_user_deposit(&env, &user)
-            .checked_add(amount)
-            .expect("Overflow");
-        set_user_deposit(&env, &user, user_total);
+/// Book the redemption of `shares` sXLM from `owner` for `proceeds` XLM
+fn realize_position(
+    env: &Env,
+    owner: &Address,
+    shares: i128,
+    proceeds: i128,
+) -> Result<(), VaultError> {
+    let cost = remove_from_position(env, owner, shares)?;
+    let mut record = get_position(env, owner)?;
+    record.realized_pnl = record
+        .realized_pnl
+        .checked_add(proceeds - cost)
+        .ok_or(VaultError::Overflow)?;
+    set_position(env, owner, &record);
+    Ok(())
+}
 
-        // Mint sXLM to user
-        env.invoke_contract::<()>(
-            &sxlm_token,
-            &Symbol::new(&env, "mint"),
-            (user.clone(), sxlm_to_mint).into_val(&env),
-        );
+/// Remove `amount` of XLM from the deposit/yield bookkeeping, splitting it
+/// proportionally between principal and yield.
+fn reduce_assets(env: &Env, amount: i128) {
+    let total_deposits = get_total_deposits(env);
+    let yield_accrued = get_yield_accrued(env);
+    let total_assets = total_deposits + yield_accrued;
 
-        // Emit event
-        env.events().publish(
-            (Symbol::new(&env, "deposit"),),
-            (user.clone(), amount, sxlm_to_mint),
-        );
+    if total_assets > 0 {
+        let deposit_portion =
+            mul_div(amount, total_deposits, total_assets, Rounding::Floor).unwrap_or(amount);
+        let yield_portion = amount - deposit_portion;
 
-        unlock_reentrancy(&env);
-        sxlm_to_mint
+        set_total_deposits(env, total_deposits.saturating_sub(deposit_portion));
+        set_yield_accrued(env, yield_accrued.saturating_sub(yield_portion));
     }
+}
 
-    /// Deposit with slippage protection
-    pub fn deposit_with_min_out(
-        env: Env,
-        user: Address,
-        amount: i128,
-        min_sxlm_out: i128,
-    ) -> i128 {
-        let sxlm_received = Self::deposit(env.clone(), user, amount);
+// ============ Access Control ============
 
-        if sxlm_received < min_sxlm_out {
-            panic!("Slippage: received {} but expected at least {}", sxlm_received, min_sxlm_out);
-        }
+fn has_role(env: &Env, role: Role, account: &Address) -> Result<bool, VaultError> {
+    if *account == get_admin(env)? {
+        return Ok(true);
+    }
+    if role == Role::Owner {
+        return Ok(false);
+    }
+    let key = DataKey::Role(role, account.clone());
+    let granted = env.storage().persistent().has(&key);
+    if granted {
+        extend_persistent_ttl(env, &key);
+    }
+    Ok(granted)
+}
 
-        sxlm_received
+/// Authorize `caller` and check it holds `role` (or is the owner)
+fn require_role(env: &Env, caller: &Address, role: Role) -> Result<(), VaultError> {
+    caller.require_auth();
+    if has_role(env, role, caller)? {
+        Ok(())
+    } else {
+        Err(VaultError::Unauthorized)
     }
+}
 
-    /// Withdraw XLM by burning sXLM
-    /// Returns the amount of XLM returned
-    pub fn withdraw(env: Env, user: Address, sxlm_amount: i128) -> i128 {
-        extend_instance_ttl(&env);
-        require_not_reentering(&env);
-        lock_reentrancy(&env);
+// ============ Schema Versioning ============
 
-        // Validations
-        user.require_auth();
+/// Stored schema version; deployments from before versioning read as 0
+fn get_schema_version(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::SchemaVersion)
+        .unwrap_or(0)
+}
 
-        if is_paused(&env) {
-            unlock_reentrancy(&env);
-            panic!("Vault is paused");
-        }
+/// Refuse to change state while storage is behind the code (run `migrate`)
+fn require_current_schema(env: &Env) -> Result<(), VaultError> {
+    get_admin(env)?;
+    if get_schema_version(env) < SCHEMA_VERSION {
+        return Err(VaultError::SchemaOutdated);
+    }
+    Ok(())
+}
 
-        if sxlm_amount < MIN_AMOUNT {
-            unlock_reentrancy(&env);
-            panic!("Amount below minimum");
-        }
+/// Transform storage from `version - 1` to `version`
+fn migrate_step(env: &Env, version: u32) -> Result<(), VaultError> {
+    match version {
+        1 => migrate_v1(env),
+        2 => migrate_v2(env),
+        _ => return Err(VaultError::InvalidSchemaVersion),
+    }
+    Ok(())
+}
 
-        let xlm_token = get_xlm_token(&env);
-        let sxlm_token = get_sxlm_token(&env);
+/// Pre-versioning deployments never stamped the fee accrual clock
+fn migrate_v1(env: &Env) {
+    if !env.storage().instance().has(&DataKey::LastFeeAccrual) {
+        env.storage()
+            .instance()
+            .set(&DataKey::LastFeeAccrual, &env.ledger().timestamp());
+    }
+}
 
-        // Check user has enough sXLM
-        let sxlm_client = token::Client::new(&env, &sxlm_token);
-        let user_sxlm_balance = sxlm_client.balance(&user);
-        if user_sxlm_balance < sxlm_amount {
-            unlock_reentrancy(&env);
-            panic!("Insufficient sXLM balance");
+/// The single `Paused` flag becomes per-operation flags
+fn migrate_v2(env: &Env) {
+    let paused: bool = env
+        .storage()
+        .instance()
+        .get(&DataKey::Paused)
+        .unwrap_or(false);
+    if paused {
+        for operation in VAULT_OPERATIONS {
+            env.storage()
+                .instance()
+                .set(&DataKey::PauseFlag(operation), &true);
         }
+    }
+    env.storage().instance().remove(&DataKey::Paused);
+}
 
-        // Calculate XLM to return based on exchange rate
-        let exchange_rate = Self::get_exchange_rate(env.clone());
-        let xlm_to_return = sxlm_amount
-            .checked_mul(exchange_rate)
-            .expect("Overflow")
-            .checked_div(PRECISION)
-            .expect("Division error");
+// ============ Timelock ============
 
-        if xlm_to_return == 0 {
-            unlock_reentrancy(&env);
-            panic!("Withdrawal too small");
-        }
+fn get_timelock_delay(env: &Env) -> u64 {
+    env.storage()
+        .instance()
+        .get(&DataKey::TimelockDelay)
+        .unwrap_or(DEFAULT_TIMELOCK_DELAY)
+}
 
-        // Check vault has enough XLM
-        let xlm_client = token::Client::new(&env, &xlm_token);
-        let vault_xlm_balance = xlm_client.balance(&env.current_contract_address());
-        if vault_xlm_balance < xlm_to_return {
-            unlock_reentrancy(&env);
-            panic!("Insufficient vault liquidity");
-        }
+fn get_timelock_ops(env: &Env) -> Vec<u64> {
+    env.storage()
+        .instance()
+        .get(&DataKey::TimelockOps)
+        .unwrap_or(Vec::new(env))
+}
 
-        // Burn sXLM from user
-        env.invoke_contract::<()>(
-            &sxlm_token,
-            &Symbol::new(&env, "burn"),
-            (user.clone(), sxlm_amount).into_val(&env),
-        );
+fn set_timelock_ops(env: &Env, ops: &Vec<u64>) {
+    env.storage().instance().set(&DataKey::TimelockOps, ops);
+}
 
-        // Update total deposits and yield tracking
-        let total_deposits = get_total_deposits(&env);
-        let yield_accrued = get_yield_accrued(&env);
-        let total_assets = total_deposits + yield_accrued;
+fn get_timelock_op(env: &Env, id: u64) -> Result<TimelockOp, VaultError> {
+    let key = DataKey::TimelockOp(id);
+    let op = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .ok_or(VaultError::OperationNotFound)?;
+    extend_persistent_ttl(env, &key);
+    Ok(op)
+}
 
-        // Proportionally reduce deposits and yield
-        if total_assets > 0 {
-            let deposit_portion = (xlm_to_return * total_deposits) / total_assets;
-            let yield_portion = xlm_to_return - deposit_portion;
+/// Drop a scheduled operation from storage and the pending list
+fn remove_timelock_op(env: &Env, id: u64) {
+    env.storage().persistent().remove(&DataKey::TimelockOp(id));
+    let mut ops = get_timelock_ops(env);
+    if let Some(index) = ops.first_index_of(id) {
+        ops.remove(index);
+        set_timelock_ops(env, &ops);
+    }
+}
 
-            set_total_deposits(&env, total_deposits.saturating_sub(deposit_portion));
-            set_yield_accrued(&env, yield_accrued.saturating_sub(yield_portion));
+/// Role allowed to schedule `action`, after checking its static bounds
+fn timelock_role(action: &TimelockAction) -> Result<Role, VaultError> {
+    match action {
+        TimelockAction::Upgrade(_)
+        | TimelockAction::ProposeAdmin(_, _) => Ok(Role::Owner),
+        TimelockAction::SetTimelockDelay(delay) => {
+            if *delay > MAX_TIMELOCK_DELAY {
+                return Err(VaultError::InvalidDelay);
+            }
+            Ok(Role::Owner)
+        }
+        TimelockAction::SetTotalCap(total_cap) => {
+            if *total_cap < 0 {
+                return Err(VaultError::InvalidAmount);
+            }
+            Ok(Role::Strategist)
+        }
+        TimelockAction::SetMaxDeposit(max_deposit) => {
+            if *max_deposit < MIN_AMOUNT {
+                return Err(VaultError::BelowMinimum);
+            }
+            Ok(Role::Strategist)
+        }
+        TimelockAction::SetPerformanceFee(fee_bps) => {
+            if *fee_bps > MAX_PERFORMANCE_FEE_BPS {
+                return Err(VaultError::FeeTooHigh);
+            }
+            Ok(Role::FeeManager)
         }
+        TimelockAction::SetManagementFee(fee_bps) => {
+            if *fee_bps > MAX_MANAGEMENT_FEE_BPS {
+                return Err(VaultError::FeeTooHigh);
+            }
+            Ok(Role::FeeManager)
+        }
+    }
+}
 
-        // Update user deposit tracking
-        let user_deposit = get_user_deposit(&env, &user);
-        if user_deposit > 0 {
-            let new_user_deposit = user_deposit.saturating_sub(xlm_to_return);
-            set_user_deposit(&env, &user, new_user_deposit);
+fn apply_timelock_action(env: &Env, action: TimelockAction) -> Result<(), VaultError> {
+    match action {
+        TimelockAction::Upgrade(new_wasm_hash) => {
+            env.deployer().update_current_contract_wasm(new_wasm_hash);
+        }
+        TimelockAction::SetTotalCap(total_cap) => {
+            env.storage().instance().set(&DataKey::TotalCap, &total_cap);
         }
+        TimelockAction::SetMaxDeposit(max_deposit) => {
+            env.storage().instance().set(&DataKey::MaxDeposit, &max_deposit);
+        }
+        TimelockAction::ProposeAdmin(new_admin, expires_ledger) => {
+            let pending = PendingTransfer {
+                address: new_admin.clone(),
+                expires_ledger,
+            };
+            env.storage().instance().set(&DataKey::PendingAdmin, &pending);
+            env.events().publish(
+                (Symbol::new(env, "admin_proposed"),),
+                (get_admin(env)?, new_admin, expires_ledger),
+            );
+        }
+        TimelockAction::SetPerformanceFee(fee_bps) => {
+            if fee_bps > 0 && get_treasury(env).is_none() {
+                return Err(VaultError::TreasuryNotSet);
+            }
+            env.storage().instance().set(&DataKey::PerformanceFeeBps, &fee_bps);
+            env.events().publish(
+                (Symbol::new(env, "fee_set"), Symbol::new(env, "performance")),
+                fee_bps,
+            );
+        }
+        TimelockAction::SetManagementFee(fee_bps) => {
+            if fee_bps > 0 && get_treasury(env).is_none() {
+                return Err(VaultError::TreasuryNotSet);
+            }
+            // Settle at the old rate before switching
+            accrue_management_fee(env)?;
+            env.storage().instance().set(&DataKey::ManagementFeeBps, &fee_bps);
+            env.events().publish(
+                (Symbol::new(env, "fee_set"), Symbol::new(env, "management")),
+                fee_bps,
+            );
+        }
+        TimelockAction::SetTimelockDelay(delay) => {
+            env.storage().instance().set(&DataKey::TimelockDelay, &delay);
+        }
+    }
+    Ok(())
+}
 
-        // Transfer XLM to user
-        xlm_client.transfer(&env.current_contract_address(), &user, &xlm_to_return);
+// ============ Withdrawal Queue ============
 
-        // Emit event
-        env.events().publish(
-            (Symbol::new(&env, "withdraw"),),
-            (user.clone(), sxlm_amount, xlm_to_return),
+fn get_queue_head(env: &Env) -> u64 {
+    env.storage().instance().get(&DataKey::QueueHead).unwrap_or(0)
+}
+
+fn get_queue_tail(env: &Env) -> u64 {
+    env.storage().instance().get(&DataKey::QueueTail).unwrap_or(0)
+}
+
+fn get_pending_withdrawals(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::PendingWithdrawals)
+        .unwrap_or(0)
+}
+
+fn set_pending_withdrawals(env: &Env, amount: i128) {
+    env.storage().instance().set(&DataKey::PendingWithdrawals, &amount);
+}
+
+fn get_finalized_withdrawals(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::FinalizedWithdrawals)
+        .unwrap_or(0)
+}
+
+fn set_finalized_withdrawals(env: &Env, amount: i128) {
+    env.storage().instance().set(&DataKey::FinalizedWithdrawals, &amount);
+}
+
+fn get_ticket(env: &Env, id: u64) -> Result<WithdrawTicket, VaultError> {
+    let key = DataKey::WithdrawTicket(id);
+    let ticket = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .ok_or(VaultError::TicketNotFound)?;
+    extend_persistent_ttl(env, &key);
+    Ok(ticket)
+}
+
+fn set_ticket(env: &Env, id: u64, ticket: &WithdrawTicket) {
+    let key = DataKey::WithdrawTicket(id);
+    env.storage().persistent().set(&key, ticket);
+    extend_persistent_ttl(env, &key);
+}
+
+/// sXLM this vault has issued and not yet burned. Other minters' sXLM is
+/// backed by their own assets, so shares are priced against this count
+/// rather than the token's total supply.
+fn get_sxlm_supply(env: &Env) -> Result<i128, VaultError> {
+    Ok(env.invoke_contract(
+        &get_sxlm_token(env)?,
+        &Symbol::new(env, "minter_outstanding"),
+        (env.current_contract_address(),).into_val(env),
+    ))
+}
+
+/// Idle XLM plus the value reported by every strategy, minus XLM owed to
+/// queued withdrawals and yield that has not vested yet
+fn get_total_assets(env: &Env) -> Result<i128, VaultError> {
+    let xlm_client = token::Client::new(env, &get_xlm_token(env)?);
+    let balance = xlm_client.balance(&env.current_contract_address());
+    let deployed = get_deployed_assets(env)?;
+    balance
+        .checked_add(deployed)
+        .ok_or(VaultError::Overflow)?
+        .checked_sub(get_pending_withdrawals(env))
+        .ok_or(VaultError::Overflow)?
+        .checked_sub(get_locked_yield(env))
+        .ok_or(VaultError::Overflow)
+}
+
+/// sXLM equivalent of `assets` XLM, computed from total assets and supply
+fn convert_to_shares(env: &Env, assets: i128, rounding: Rounding) -> Result<i128, VaultError> {
+    let supply = get_sxlm_supply(env)? + VIRTUAL_SHARES;
+    let total_assets = get_total_assets(env)? + VIRTUAL_ASSETS;
+    mul_div(assets, supply, total_assets, rounding)
+}
+
+/// XLM equivalent of `shares` sXLM, computed from total assets and supply
+fn convert_to_assets(env: &Env, shares: i128, rounding: Rounding) -> Result<i128, VaultError> {
+    let supply = get_sxlm_supply(env)? + VIRTUAL_SHARES;
+    let total_assets = get_total_assets(env)? + VIRTUAL_ASSETS;
+    mul_div(shares, total_assets, supply, rounding)
+}
+
+fn get_sxlm_balance(env: &Env, user: &Address) -> Result<i128, VaultError> {
+    Ok(token::Client::new(env, &get_sxlm_token(env)?).balance(user))
+}
+
+/// sXLM `user` holds that this vault issued, the most it can redeem for them
+fn get_redeemable_shares(env: &Env, user: &Address) -> Result<i128, VaultError> {
+    Ok(env.invoke_contract::<i128>(
+        &get_sxlm_token(env)?,
+        &Symbol::new(env, "issued_balance"),
+        (user.clone(), env.current_contract_address()).into_val(env),
+    ))
+}
+
+/// Whether the sXLM token's compliance freeze covers `user`
+fn is_sxlm_frozen(env: &Env, user: &Address) -> Result<bool, VaultError> {
+    Ok(env.invoke_contract::<bool>(
+        &get_sxlm_token(env)?,
+        &Symbol::new(env, "is_frozen"),
+        (user.clone(),).into_val(env),
+    ))
+}
+
+// ============ Yield Vesting ============
+
+fn get_vesting_period(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::VestingPeriod)
+        .unwrap_or(DEFAULT_VESTING_PERIOD)
+}
+
+fn get_vesting_end(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::VestingEnd).unwrap_or(0)
+}
+
+/// Portion of added yield that has not unlocked at the current ledger
+fn get_locked_yield(env: &Env) -> i128 {
+    let now = env.ledger().sequence();
+    let end = get_vesting_end(env);
+    if now >= end {
+        return 0;
+    }
+
+    let locked: i128 = env.storage().instance().get(&DataKey::LockedYield).unwrap_or(0);
+    let start: u32 = env.storage().instance().get(&DataKey::VestingStart).unwrap_or(0);
+    // Rounded up so vested yield never runs ahead of the schedule
+    mul_div(locked, (end - now) as i128, (end - start) as i128, Rounding::Ceil).unwrap_or(locked)
+}
+
+/// Restart the vesting schedule at the current ledger with `locked` yield
+fn start_vesting(env: &Env, locked: i128, period: u32) {
+    let now = env.ledger().sequence();
+    env.storage().instance().set(&DataKey::LockedYield, &locked);
+    env.storage().instance().set(&DataKey::VestingStart, &now);
+    env.storage().instance().set(&DataKey::VestingEnd, &(now + period));
+}
+
+/// XLM held by the vault that is not owed to queued withdrawals
+fn get_available_liquidity(env: &Env) -> Result<i128, VaultError> {
+    let xlm_client = token::Client::new(env, &get_xlm_token(env)?);
+    let balance = xlm_client.balance(&env.current_contract_address());
+    Ok(balance.saturating_sub(get_pending_withdrawals(env)).max(0))
+}
+
+// ============ Strategy Registry ============
+
+fn get_strategies(env: &Env) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Strategies)
+        .unwrap_or(Vec::new(env))
+}
+
+fn set_strategies(env: &Env, strategies: &Vec<Address>) {
+    env.storage().instance().set(&DataKey::Strategies, strategies);
+}
+
+fn get_strategy_config(env: &Env, strategy: &Address) -> Result<StrategyConfig, VaultError> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Strategy(strategy.clone()))
+        .ok_or(VaultError::StrategyNotFound)
+}
+
+fn set_strategy_config(env: &Env, strategy: &Address, config: &StrategyConfig) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Strategy(strategy.clone()), config);
+}
+
+fn get_idle_buffer_bps(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::IdleBufferBps)
+        .unwrap_or(0)
+}
+
+/// Sum of the idle buffer and all target weights, optionally replacing one
+/// strategy's weight
+fn total_weight_with(env: &Env, strategy: &Address, weight_bps: u32) -> Result<u32, VaultError> {
+    let mut total = weight_bps + get_idle_buffer_bps(env);
+    for other in get_strategies(env).iter() {
+        if other != *strategy {
+            total += get_strategy_config(env, &other)?.weight_bps;
+        }
+    }
+    Ok(total)
+}
+
+/// XLM value reported by all registered strategies
+fn get_deployed_assets(env: &Env) -> Result<i128, VaultError> {
+    let mut total: i128 = 0;
+    for strategy in get_strategies(env).iter() {
+        let value = StrategyClient::new(env, &strategy).balance();
+        total = total.checked_add(value).ok_or(VaultError::Overflow)?;
+    }
+    Ok(total)
+}
+
+/// Move idle XLM into a strategy. The strategy pulls the funds, so the vault
+/// pre-authorizes the XLM transfer made on its behalf.
+fn deploy_to_strategy(env: &Env, strategy: &Address, amount: i128) -> Result<i128, VaultError> {
+    let xlm_token = get_xlm_token(env)?;
+    let vault = env.current_contract_address();
+
+    env.authorize_as_current_contract(vec![
+        env,
+        InvokerContractAuthEntry::Contract(SubContractInvocation {
+            context: ContractContext {
+                contract: xlm_token,
+                fn_name: Symbol::new(env, "transfer"),
+                args: (vault.clone(), strategy.clone(), amount).into_val(env),
+            },
+            sub_invocations: Vec::new(env),
+        }),
+    ]);
+
+    let deposited = StrategyClient::new(env, strategy).deposit(&vault, &amount);
+    env.events().publish(
+        (Symbol::new(env, "strategy_deposit"), strategy.clone()),
+        deposited,
+    );
+    Ok(deposited)
+}
+
+/// `amount` of `bps` basis points, rounded down
+fn bps_of(amount: i128, bps: u32) -> Result<i128, VaultError> {
+    mul_div(amount, bps as i128, BPS_DENOMINATOR as i128, Rounding::Floor)
+}
+
+/// Pull XLM back from a strategy into idle balance
+fn recall_from_strategy(env: &Env, strategy: &Address, amount: i128) -> i128 {
+    let vault = env.current_contract_address();
+    let received = StrategyClient::new(env, strategy).withdraw(&vault, &amount);
+    env.events().publish(
+        (Symbol::new(env, "strategy_withdraw"), strategy.clone()),
+        received,
+    );
+    received
+}
+
+// ============ Fees ============
+
+fn get_treasury(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&DataKey::Treasury)
+}
+
+fn get_performance_fee_bps(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::PerformanceFeeBps)
+        .unwrap_or(0)
+}
+
+fn get_management_fee_bps(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::ManagementFeeBps)
+        .unwrap_or(0)
+}
+
+fn get_last_fee_accrual(env: &Env) -> u64 {
+    env.storage()
+        .instance()
+        .get(&DataKey::LastFeeAccrual)
+        .unwrap_or(env.ledger().timestamp())
+}
+
+/// Management fee (in XLM) accrued on `total_assets` since the last accrual
+fn pending_management_fee(env: &Env, total_assets: i128) -> Result<i128, VaultError> {
+    let elapsed = env.ledger().timestamp().saturating_sub(get_last_fee_accrual(env));
+    let fee_bps = get_management_fee_bps(env);
+    if elapsed == 0 || fee_bps == 0 || total_assets <= 0 {
+        return Ok(0);
+    }
+
+    let fee = mul_div(
+        bps_of(total_assets, fee_bps)?,
+        elapsed as i128,
+        SECONDS_PER_YEAR as i128,
+        Rounding::Floor,
+    )?;
+    Ok(fee.min(total_assets))
+}
+
+/// Mint sXLM to the treasury worth `fee_assets` out of `total_assets` (which
+/// includes the fee), diluting holders by exactly the fee. Returns the shares
+/// minted. Nothing is minted after shutdown, whose snapshot fixed the supply
+/// that the recovered XLM is split across.
+fn mint_fee_shares(
+    env: &Env,
+    fee_assets: i128,
+    total_assets: i128,
+    kind: &str,
+) -> Result<i128, VaultError> {
+    if is_shutdown(env) {
+        return Ok(0);
+    }
+    let treasury = match get_treasury(env) {
+        Some(treasury) => treasury,
+        None => return Ok(0),
+    };
+    let supply = get_sxlm_supply(env)?;
+    if fee_assets <= 0 || supply == 0 || total_assets <= fee_assets {
+        return Ok(0);
+    }
+
+    let shares = mul_div(
+        fee_assets,
+        supply + VIRTUAL_SHARES,
+        total_assets - fee_assets + VIRTUAL_ASSETS,
+        Rounding::Floor,
+    )?;
+    if shares == 0 {
+        return Ok(0);
+    }
+
+    // Fee shares are booked at the value they were minted for
+    add_to_position(env, &treasury, shares, fee_assets)?;
+    env.invoke_contract::<()>(
+        &get_sxlm_token(env)?,
+        &Symbol::new(env, "mint"),
+        (env.current_contract_address(), treasury.clone(), shares).into_val(env),
+    );
+    env.events().publish(
+        (Symbol::new(env, "fees_accrued"), Symbol::new(env, kind)),
+        (treasury, fee_assets, shares),
+    );
+    Ok(shares)
+}
+
+/// Settle the management fee up to the current ledger timestamp
+fn accrue_management_fee(env: &Env) -> Result<(), VaultError> {
+    let total_assets = get_total_assets(env)?;
+    let fee = pending_management_fee(env, total_assets)?;
+    env.storage()
+        .instance()
+        .set(&DataKey::LastFeeAccrual, &env.ledger().timestamp());
+    mint_fee_shares(env, fee, total_assets, "management")?;
+    Ok(())
+}
+
+// ============ Emergency Shutdown ============
+
+/// Pull everything a strategy will return, tolerating a strategy that traps
+/// or lies. Returns `(reported, recovered)`; a strategy whose `balance` call
+/// fails is treated as reporting nothing.
+fn recover_strategy(env: &Env, strategy: &Address) -> (i128, i128) {
+    let client = StrategyClient::new(env, strategy);
+    let reported = match client.try_balance() {
+        Ok(Ok(value)) => value.max(0),
+        _ => 0,
+    };
+    if reported == 0 {
+        return (0, 0);
+    }
+
+    let vault = env.current_contract_address();
+    let recovered = match client.try_withdraw(&vault, &reported) {
+        Ok(Ok(received)) => received.clamp(0, reported),
+        _ => 0,
+    };
+    env.events().publish(
+        (Symbol::new(env, "strategy_withdraw"), strategy.clone()),
+        recovered,
+    );
+    (reported, recovered)
+}
+
+fn get_strategy_loss(env: &Env, strategy: &Address) -> i128 {
+    let key = DataKey::StrategyLoss(strategy.clone());
+    let loss = env.storage().persistent().get(&key).unwrap_or(0);
+    if loss > 0 {
+        extend_persistent_ttl(env, &key);
+    }
+    loss
+}
+
+// ============ Outflow Limit ============
+
+fn get_outflow_limit(env: &Env) -> Option<OutflowLimit> {
+    env.storage().instance().get(&DataKey::OutflowLimit)
+}
+
+/// Outflow still counted against the window at the current ledger
+fn get_outflow_used(env: &Env, window_ledgers: u32) -> i128 {
+    let used: i128 = env.storage().instance().get(&DataKey::OutflowUsed).unwrap_or(0);
+    let updated: u32 = env.storage().instance().get(&DataKey::OutflowUpdated).unwrap_or(0);
+    let elapsed = env.ledger().sequence().saturating_sub(updated);
+    if elapsed >= window_ledgers {
+        return 0;
+    }
+    // Rounded up so the limit never frees capacity early
+    mul_div(
+        used,
+        (window_ledgers - elapsed) as i128,
+        window_ledgers as i128,
+        Rounding::Ceil,
+    )
+    .unwrap_or(used)
+}
+
+/// XLM that may still leave through instant withdrawals this window
+/// (`i128::MAX` when no limit is set), ignoring idle liquidity
+fn get_outflow_room(env: &Env) -> Result<i128, VaultError> {
+    let limit = match get_outflow_limit(env) {
+        Some(limit) => limit,
+        None => return Ok(i128::MAX),
+    };
+    let mut max = limit.max_amount.unwrap_or(i128::MAX);
+    if let Some(bps) = limit.max_bps {
+        max = max.min(bps_of(get_total_assets(env)?.max(0), bps)?);
+    }
+    Ok(max
+        .saturating_sub(get_outflow_used(env, limit.window_ledgers))
+        .max(0))
+}
+
+fn record_outflow(env: &Env, amount: i128) -> Result<(), VaultError> {
+    let limit = match get_outflow_limit(env) {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    let used = get_outflow_used(env, limit.window_ledgers)
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    env.storage().instance().set(&DataKey::OutflowUsed, &used);
+    env.storage()
+        .instance()
+        .set(&DataKey::OutflowUpdated, &env.ledger().sequence());
+    Ok(())
+}
+
+/// XLM that can be withdrawn instantly right now
+fn get_withdraw_capacity(env: &Env) -> Result<i128, VaultError> {
+    if is_paused(env, Operation::Withdraw) {
+        return Ok(0);
+    }
+    let liquidity = get_available_liquidity(env)?;
+    if is_withdraw_only(env) {
+        // Pro-rata redemptions are not rate limited
+        return Ok(liquidity);
+    }
+    Ok(get_outflow_room(env)?.min(liquidity))
+}
+
+// ============ Views ============
+
+fn get_exchange_rate(env: &Env) -> Result<i128, VaultError> {
+    // Same as converting one whole sXLM; 1:1 while the vault is empty
+    convert_to_assets(env, PRECISION, Rounding::Floor)
+}
+
+fn get_fee_preview(env: &Env, total_assets: i128) -> Result<FeePreview, VaultError> {
+    Ok(FeePreview {
+        treasury: get_treasury(env),
+        performance_fee_bps: get_performance_fee_bps(env),
+        management_fee_bps: get_management_fee_bps(env),
+        pending_management_fee: pending_management_fee(env, total_assets)?,
+        last_accrual: get_last_fee_accrual(env),
+    })
+}
+
+fn get_pause_status(env: &Env) -> Result<PauseStatus, VaultError> {
+    let transfers = env.invoke_contract::<bool>(
+        &get_sxlm_token(env)?,
+        &Symbol::new(env, "transfers_paused"),
+        vec![env],
+    );
+    Ok(PauseStatus {
+        deposits: get_pause_flag(env, Operation::Deposit),
+        withdrawals: get_pause_flag(env, Operation::Withdraw),
+        yield_additions: get_pause_flag(env, Operation::Yield),
+        strategies: get_pause_flag(env, Operation::Strategy),
+        transfers,
+        withdraw_only: is_withdraw_only(env),
+        shutdown: is_shutdown(env),
+    })
+}
+
+/// Maximum XLM `user` can deposit right now under every limit
+fn max_deposit_for(env: &Env, user: &Address) -> Result<i128, VaultError> {
+    if is_paused(env, Operation::Deposit) {
+        return Ok(0);
+    }
+    let cap_room = get_total_cap(env).saturating_sub(get_total_deposits(env));
+    Ok(get_max_deposit(env)
+        .min(cap_room)
+        .min(deposit_headroom(env, user)?)
+        .max(0))
+}
+
+/// Maximum XLM `user` can withdraw instantly right now
+fn max_withdraw_for(env: &Env, user: &Address) -> Result<i128, VaultError> {
+    if is_paused(env, Operation::Withdraw) || is_withdraw_only(env) || is_sxlm_frozen(env, user)? {
+        return Ok(0);
+    }
+    let owned = convert_to_assets(env, get_redeemable_shares(env, user)?, Rounding::Floor)?;
+    Ok(owned.min(get_withdraw_capacity(env)?))
+}
+
+/// `user`'s sXLM, its current value, cost basis and PnL
+fn get_position_view(env: &Env, user: &Address) -> Result<Position, VaultError> {
+    let record = get_position(env, user)?;
+    let shares = get_sxlm_balance(env, user)?;
+    let value = convert_to_assets(env, shares, Rounding::Floor)?;
+    let (cost_basis, avg_cost) = if record.shares > 0 {
+        // sXLM burned outside the vault leaves the record ahead of the balance
+        let held = shares.min(record.shares);
+        (
+            mul_div(record.cost_basis, held, record.shares, Rounding::Floor)?,
+            mul_div(record.cost_basis, PRECISION, record.shares, Rounding::Floor)?,
+        )
+    } else {
+        (0, 0)
+    };
+    Ok(Position {
+        shares,
+        value,
+        cost_basis,
+        avg_cost,
+        unrealized_pnl: value - cost_basis,
+        realized_pnl: record.realized_pnl,
+    })
+}
+
+// ============ Deposit / Withdraw ============
+
+/// Validate a deposit of `amount` XLM into `receiver`'s position against
+/// pause state, vault-wide limits and per-address limits
+fn check_deposit(env: &Env, receiver: &Address, amount: i128) -> Result<(), VaultError> {
+    require_not_paused(env, Operation::Deposit)?;
+
+    if amount < MIN_AMOUNT {
+        return Err(VaultError::BelowMinimum);
+    }
+
+    if amount > get_max_deposit(env) {
+        return Err(VaultError::AboveMaxDeposit);
+    }
+
+    let total_after = get_total_deposits(env)
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    if total_after > get_total_cap(env) {
+        return Err(VaultError::CapExceeded);
+    }
+
+    if is_allowlist_enabled(env) && !is_allowlisted(env, receiver) {
+        return Err(VaultError::NotAllowlisted);
+    }
+    if amount > deposit_headroom(env, receiver)? {
+        return Err(VaultError::UserCapExceeded);
+    }
+    Ok(())
+}
+
+/// Deposit `amount` XLM from `payer`, minting the sXLM to `receiver`
+fn deposit_assets(
+    env: &Env,
+    payer: &Address,
+    receiver: &Address,
+    amount: i128,
+) -> Result<i128, VaultError> {
+    check_deposit(env, receiver, amount)?;
+    accrue_management_fee(env)?;
+
+    // Calculate sXLM to mint based on exchange rate (rounded down)
+    let sxlm_to_mint = convert_to_shares(env, amount, Rounding::Floor)?;
+
+    if sxlm_to_mint == 0 {
+        return Err(VaultError::DepositTooSmall);
+    }
+
+    settle_deposit(env, payer, receiver, amount, sxlm_to_mint)?;
+    Ok(sxlm_to_mint)
+}
+
+/// Redeem `shares` sXLM held by `owner`, paying the XLM to `receiver`.
+/// With a `spender`, the burn is charged against the owner's sXLM allowance.
+/// A frozen receiver cannot be paid, so redemptions cannot route around a freeze.
+fn redeem_shares(
+    env: &Env,
+    spender: Option<&Address>,
+    owner: &Address,
+    receiver: &Address,
+    shares: i128,
+) -> Result<i128, VaultError> {
+    require_not_paused(env, Operation::Withdraw)?;
+    if receiver != owner && is_sxlm_frozen(env, receiver)? {
+        return Err(VaultError::AccountFrozen);
+    }
+
+    if shares < MIN_AMOUNT {
+        return Err(VaultError::BelowMinimum);
+    }
+
+    // Check owner has enough sXLM, and the spender enough allowance
+    if get_redeemable_shares(env, owner)? < shares {
+        return Err(VaultError::InsufficientBalance);
+    }
+    if let Some(spender) = spender {
+        let sxlm_client = token::Client::new(env, &get_sxlm_token(env)?);
+        if sxlm_client.allowance(owner, spender) < shares {
+            return Err(VaultError::InsufficientAllowance);
+        }
+    }
+    accrue_management_fee(env)?;
+
+    // Calculate XLM to return based on exchange rate (rounded down)
+    let xlm_to_return = if is_withdraw_only(env) {
+        redeem_pro_rata(env, shares)?
+    } else {
+        convert_to_assets(env, shares, Rounding::Floor)?
+    };
+
+    if xlm_to_return == 0 {
+        return Err(VaultError::WithdrawalTooSmall);
+    }
+
+    if is_withdraw_only(env) {
+        settle_withdraw(env, spender, owner, receiver, shares, xlm_to_return)?;
+    } else {
+        settle_or_queue(env, spender, owner, receiver, shares, xlm_to_return)?;
+    }
+    Ok(xlm_to_return)
+}
+
+/// Withdraw-only mode: value `shares` as their slice of idle XLM plus the same
+/// slice recalled from every strategy, so early exits cannot drain the idle
+/// balance ahead of later ones and strategy losses are shared as realized
+fn redeem_pro_rata(env: &Env, shares: i128) -> Result<i128, VaultError> {
+    let supply = get_sxlm_supply(env)? + VIRTUAL_SHARES;
+    let idle = get_available_liquidity(env)?
+        .saturating_sub(get_locked_yield(env))
+        .max(0);
+
+    let mut payout = mul_div(idle, shares, supply, Rounding::Floor)?;
+    for strategy in get_strategies(env).iter() {
+        let position = StrategyClient::new(env, &strategy).balance();
+        let slice = mul_div(position, shares, supply, Rounding::Floor)?;
+        if slice > 0 {
+            payout = payout
+                .checked_add(recall_from_strategy(env, &strategy, slice))
+                .ok_or(VaultError::Overflow)?;
+        }
+    }
+    Ok(payout)
+}
+
+/// Pull `amount` XLM from `payer` and mint `shares` sXLM to `receiver`
+fn settle_deposit(
+    env: &Env,
+    payer: &Address,
+    receiver: &Address,
+    amount: i128,
+    shares: i128,
+) -> Result<(), VaultError> {
+    // Transfer XLM from payer to vault
+    let xlm_client = token::Client::new(env, &get_xlm_token(env)?);
+    xlm_client.transfer(payer, &env.current_contract_address(), &amount);
+
+    // Update total deposits
+    let total_after = get_total_deposits(env)
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    set_total_deposits(env, total_after);
+
+    // Track cost against the receiver, who holds the position
+    add_to_position(env, receiver, shares, amount)?;
+
+    // Mint sXLM to receiver
+    env.invoke_contract::<()>(
+        &get_sxlm_token(env)?,
+        &Symbol::new(env, "mint"),
+        (env.current_contract_address(), receiver.clone(), shares).into_val(env),
+    );
+
+    env.events().publish(
+        (Symbol::new(env, "deposit"),),
+        (receiver.clone(), amount, shares),
+    );
+    Ok(())
+}
+
+/// Pay an instant withdrawal if it fits under the outflow limit, otherwise
+/// queue it as a withdrawal ticket owned by `receiver`
+fn settle_or_queue(
+    env: &Env,
+    spender: Option<&Address>,
+    owner: &Address,
+    receiver: &Address,
+    shares: i128,
+    amount: i128,
+) -> Result<(), VaultError> {
+    if amount > get_outflow_room(env)? {
+        queue_withdraw(env, spender, owner, receiver, shares, amount)?;
+    } else {
+        record_outflow(env, amount)?;
+        settle_withdraw(env, spender, owner, receiver, shares, amount)?;
+    }
+    Ok(())
+}
+
+/// Burn `shares` sXLM from `owner`, through the owner's allowance if a
+/// `spender` is given. Every redemption path burns here, as this vault's
+/// minter, so frozen owners cannot redeem their sXLM for XLM and every
+/// redemption frees the vault's mint allowance.
+fn burn_shares(
+    env: &Env,
+    spender: Option<&Address>,
+    owner: &Address,
+    shares: i128,
+) -> Result<(), VaultError> {
+    if is_sxlm_frozen(env, owner)? {
+        return Err(VaultError::AccountFrozen);
+    }
+    let sxlm_token = get_sxlm_token(env)?;
+    match spender {
+        Some(spender) => env.invoke_contract::<()>(
+            &sxlm_token,
+            &Symbol::new(env, "minter_burn_from"),
+            (env.current_contract_address(), spender.clone(), owner.clone(), shares).into_val(env),
+        ),
+        None => env.invoke_contract::<()>(
+            &sxlm_token,
+            &Symbol::new(env, "minter_burn"),
+            (env.current_contract_address(), owner.clone(), shares).into_val(env),
+        ),
+    }
+    Ok(())
+}
+
+/// Burn `shares` sXLM from `owner` now and issue a ticket to `receiver` for
+/// `amount` XLM, payable once the queue reaches it. Returns the ticket id.
+fn queue_withdraw(
+    env: &Env,
+    spender: Option<&Address>,
+    owner: &Address,
+    receiver: &Address,
+    shares: i128,
+    amount: i128,
+) -> Result<u64, VaultError> {
+    // Positions are booked before the burn changes the owner's balance
+    realize_position(env, owner, shares, amount)?;
+    burn_shares(env, spender, owner, shares)?;
+
+    // Move the redeemed XLM out of vault assets and into queue liabilities
+    reduce_assets(env, amount);
+    let pending = get_pending_withdrawals(env)
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    set_pending_withdrawals(env, pending);
+
+    let ticket_id = get_queue_tail(env);
+    let ticket = WithdrawTicket {
+        owner: receiver.clone(),
+        sxlm_amount: shares,
+        xlm_amount: amount,
+        requested_ledger: env.ledger().sequence(),
+        finalized: false,
+    };
+    set_ticket(env, ticket_id, &ticket);
+    env.storage().instance().set(&DataKey::QueueTail, &(ticket_id + 1));
+
+    env.events().publish(
+        (Symbol::new(env, "withdraw_requested"),),
+        (receiver.clone(), ticket_id, shares, amount),
+    );
+    Ok(ticket_id)
+}
+
+/// Burn `shares` sXLM from `owner` and pay `amount` XLM to `receiver` from
+/// idle liquidity. A `spender` burns through the owner's sXLM allowance.
+fn settle_withdraw(
+    env: &Env,
+    spender: Option<&Address>,
+    owner: &Address,
+    receiver: &Address,
+    shares: i128,
+    amount: i128,
+) -> Result<(), VaultError> {
+    // Check vault has enough XLM that is not owed to the queue
+    if get_available_liquidity(env)? < amount {
+        return Err(VaultError::InsufficientLiquidity);
+    }
+
+    // Update owner deposit tracking, before the burn changes their balance
+    realize_position(env, owner, shares, amount)?;
+
+    // Burn sXLM from owner
+    burn_shares(env, spender, owner, shares)?;
+
+    // Proportionally reduce deposits and yield
+    reduce_assets(env, amount);
+
+    // Transfer XLM to receiver
+    let xlm_client = token::Client::new(env, &get_xlm_token(env)?);
+    xlm_client.transfer(&env.current_contract_address(), receiver, &amount);
+
+    env.events().publish(
+        (Symbol::new(env, "withdraw"),),
+        (owner.clone(), shares, amount),
+    );
+    Ok(())
+}
+
+// ============ Reentrancy Guard ============
+// Returning an error rolls back every storage write made by the invocation,
+// including the lock, so early returns do not need to unlock explicitly.
+
+fn require_not_reentering(env: &Env) -> Result<(), VaultError> {
+    let state: ReentrancyState = env
+        .storage()
+        .instance()
+        .get(&DataKey::Reentrancy)
+        .unwrap_or(ReentrancyState::Unlocked);
+
+    if state == ReentrancyState::Locked {
+        return Err(VaultError::Reentrant);
+    }
+    Ok(())
+}
+
+fn lock_reentrancy(env: &Env) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Reentrancy, &ReentrancyState::Locked);
+}
+
+fn unlock_reentrancy(env: &Env) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Reentrancy, &ReentrancyState::Unlocked);
+}
+
+#[contract]
+pub struct Vault;
+
+#[contractimpl]
+impl Vault {
+    /// Initialize the vault
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        xlm_token: Address,
+        sxlm_token: Address,
+    ) -> Result<(), VaultError> {
+        if env.storage().instance().has(&DataKey::Initialized) {
+            return Err(VaultError::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::XlmToken, &xlm_token);
+        env.storage().instance().set(&DataKey::SxlmToken, &sxlm_token);
+        env.storage().instance().set(&DataKey::Initialized, &true);
+        env.storage().instance().set(&DataKey::MaxDeposit, &DEFAULT_MAX_DEPOSIT);
+        env.storage().instance().set(&DataKey::Reentrancy, &ReentrancyState::Unlocked);
+        env.storage()
+            .instance()
+            .set(&DataKey::LastFeeAccrual, &env.ledger().timestamp());
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        set_total_deposits(&env, 0);
+        set_yield_accrued(&env, 0);
+        extend_instance_ttl(&env);
+        Ok(())
+    }
+
+    /// Run the migration steps that bring storage up to `target_version`
+    /// after an upgrade (owner only). `target_version` must be the version
+    /// of the running code, so each upgrade migrates exactly once.
+    pub fn migrate(env: Env, target_version: u32) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        let from_version = get_schema_version(&env);
+        if target_version != SCHEMA_VERSION || from_version >= target_version {
+            return Err(VaultError::InvalidSchemaVersion);
+        }
+
+        for version in (from_version + 1)..=target_version {
+            migrate_step(&env, version)?;
+        }
+        env.storage().instance().set(&DataKey::SchemaVersion, &target_version);
+
+        env.events().publish(
+            (Symbol::new(&env, "migrated"),),
+            (from_version, target_version),
+        );
+        Ok(())
+    }
+
+    /// Get the storage schema version
+    pub fn schema_version(env: Env) -> u32 {
+        extend_instance_ttl(&env);
+        get_schema_version(&env)
+    }
+
+    /// Deposit XLM and receive sXLM
+    /// Returns the amount of sXLM minted
+    pub fn deposit(env: Env, user: Address, amount: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        user.require_auth();
+        let sxlm_to_mint = deposit_assets(&env, &user, &user, amount)?;
+
+        unlock_reentrancy(&env);
+        Ok(sxlm_to_mint)
+    }
+
+    /// Deposit XLM from `payer` and mint the sXLM to `receiver`
+    /// Returns the amount of sXLM minted
+    pub fn deposit_for(
+        env: Env,
+        payer: Address,
+        receiver: Address,
+        amount: i128,
+    ) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        payer.require_auth();
+        let sxlm_to_mint = deposit_assets(&env, &payer, &receiver, amount)?;
+
+        unlock_reentrancy(&env);
+        Ok(sxlm_to_mint)
+    }
+
+    /// Mint exactly `shares` sXLM, paying whatever XLM they cost
+    /// Returns the amount of XLM paid
+    pub fn mint(env: Env, user: Address, shares: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        user.require_auth();
+        if shares <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        accrue_management_fee(&env)?;
+
+        // XLM cost is rounded up so the vault never under-collects
+        let amount = convert_to_assets(&env, shares, Rounding::Ceil)?;
+        check_deposit(&env, &user, amount)?;
+        settle_deposit(&env, &user, &user, amount, shares)?;
+
+        unlock_reentrancy(&env);
+        Ok(amount)
+    }
+
+    /// Deposit with slippage protection
+    pub fn deposit_with_min_out(
+        env: Env,
+        user: Address,
+        amount: i128,
+        min_sxlm_out: i128,
+    ) -> Result<i128, VaultError> {
+        let sxlm_received = Self::deposit(env.clone(), user, amount)?;
+
+        if sxlm_received < min_sxlm_out {
+            return Err(VaultError::Slippage);
+        }
+
+        Ok(sxlm_received)
+    }
+
+    /// Withdraw XLM by burning sXLM
+    /// Returns the amount of XLM returned. Above the outflow limit the XLM is
+    /// queued as a withdrawal ticket instead (see `withdraw_requested`).
+    pub fn withdraw(env: Env, user: Address, sxlm_amount: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        user.require_auth();
+        let xlm_to_return = redeem_shares(&env, None, &user, &user, sxlm_amount)?;
+
+        unlock_reentrancy(&env);
+        Ok(xlm_to_return)
+    }
+
+    /// Burn `owner`'s sXLM and send the XLM to `receiver`
+    /// Returns the amount of XLM returned, or queued for `receiver` above the
+    /// outflow limit
+    pub fn redeem_to(
+        env: Env,
+        owner: Address,
+        receiver: Address,
+        shares: i128,
+    ) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        owner.require_auth();
+        let xlm_to_return = redeem_shares(&env, None, &owner, &receiver, shares)?;
+
+        unlock_reentrancy(&env);
+        Ok(xlm_to_return)
+    }
+
+    /// Redeem `owner`'s sXLM as an approved `spender`, sending the XLM to
+    /// `receiver`. Consumes the spender's sXLM allowance from `owner`.
+    /// Returns the amount of XLM returned, or queued for `receiver` above the
+    /// outflow limit
+    pub fn redeem_from(
+        env: Env,
+        spender: Address,
+        owner: Address,
+        receiver: Address,
+        shares: i128,
+    ) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        spender.require_auth();
+        let xlm_to_return = redeem_shares(&env, Some(&spender), &owner, &receiver, shares)?;
+
+        unlock_reentrancy(&env);
+        Ok(xlm_to_return)
+    }
+
+    /// Withdraw exactly `xlm_amount` XLM, burning whatever sXLM it costs
+    /// Returns the amount of sXLM burned. Above the outflow limit the XLM is
+    /// queued instead.
+    pub fn withdraw_assets(env: Env, user: Address, xlm_amount: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        user.require_auth();
+
+        // Exact-amount exits cannot be pro-rata, so withdraw-only mode blocks them
+        require_not_paused(&env, Operation::Withdraw)?;
+        if is_withdraw_only(&env) {
+            return Err(VaultError::Paused);
+        }
+
+        if xlm_amount < MIN_AMOUNT {
+            return Err(VaultError::BelowMinimum);
+        }
+        accrue_management_fee(&env)?;
+
+        // sXLM burned is rounded up so the vault never over-pays
+        let sxlm_amount = convert_to_shares(&env, xlm_amount, Rounding::Ceil)?;
+        if get_redeemable_shares(&env, &user)? < sxlm_amount {
+            return Err(VaultError::InsufficientBalance);
+        }
+
+        settle_or_queue(&env, None, &user, &user, sxlm_amount, xlm_amount)?;
+
+        unlock_reentrancy(&env);
+        Ok(sxlm_amount)
+    }
+
+    /// Withdraw with slippage protection
+    pub fn withdraw_with_min_out(
+        env: Env,
+        user: Address,
+        sxlm_amount: i128,
+        min_xlm_out: i128,
+    ) -> Result<i128, VaultError> {
+        let xlm_received = Self::withdraw(env.clone(), user, sxlm_amount)?;
+
+        if xlm_received < min_xlm_out {
+            return Err(VaultError::Slippage);
+        }
+
+        Ok(xlm_received)
+    }
+
+    /// Queue a withdrawal for when the vault lacks idle liquidity.
+    /// Burns the sXLM now at the current exchange rate and returns the ticket id.
+    pub fn request_withdraw(env: Env, user: Address, sxlm_amount: i128) -> Result<u64, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        user.require_auth();
+
+        // Tickets lock in the current rate, so withdraw-only mode blocks them
+        require_not_paused(&env, Operation::Withdraw)?;
+        if is_withdraw_only(&env) {
+            return Err(VaultError::Paused);
+        }
+
+        if sxlm_amount < MIN_AMOUNT {
+            return Err(VaultError::BelowMinimum);
+        }
+
+        if get_redeemable_shares(&env, &user)? < sxlm_amount {
+            return Err(VaultError::InsufficientBalance);
+        }
+        accrue_management_fee(&env)?;
+
+        let xlm_owed = convert_to_assets(&env, sxlm_amount, Rounding::Floor)?;
+
+        if xlm_owed == 0 {
+            return Err(VaultError::WithdrawalTooSmall);
+        }
+
+        let ticket_id = queue_withdraw(&env, None, &user, &user, sxlm_amount, xlm_owed)?;
+
+        unlock_reentrancy(&env);
+        Ok(ticket_id)
+    }
+
+    /// Finalize queued tickets in FIFO order while idle liquidity covers them.
+    /// Callable by anyone (normally the keeper bot). Returns tickets finalized.
+    /// Tickets of frozen owners are finalized too, keeping the queue FIFO;
+    /// the XLM stays reserved until the owner is unfrozen and claims it.
+    pub fn process_queue(env: Env, max_tickets: u32) -> Result<u32, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        let xlm_client = token::Client::new(&env, &get_xlm_token(&env)?);
+        let balance = xlm_client.balance(&env.current_contract_address());
+        let mut reserved = get_finalized_withdrawals(&env);
+        let mut available = balance.saturating_sub(reserved);
+
+        let mut head = get_queue_head(&env);
+        let tail = get_queue_tail(&env);
+        let mut processed = 0u32;
+
+        while head < tail && processed < max_tickets {
+            let mut ticket = get_ticket(&env, head)?;
+            if ticket.xlm_amount > available {
+                break;
+            }
+
+            ticket.finalized = true;
+            set_ticket(&env, head, &ticket);
+            available -= ticket.xlm_amount;
+            reserved = reserved.checked_add(ticket.xlm_amount).ok_or(VaultError::Overflow)?;
+
+            env.events().publish(
+                (Symbol::new(&env, "withdraw_finalized"),),
+                (ticket.owner, head, ticket.xlm_amount),
+            );
+
+            head += 1;
+            processed += 1;
+        }
+
+        env.storage().instance().set(&DataKey::QueueHead, &head);
+        set_finalized_withdrawals(&env, reserved);
+
+        unlock_reentrancy(&env);
+        Ok(processed)
+    }
+
+    /// Pay out a finalized withdrawal ticket to its owner, unless frozen
+    pub fn claim_withdraw(env: Env, user: Address, ticket_id: u64) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        user.require_auth();
+
+        let ticket = get_ticket(&env, ticket_id)?;
+        if ticket.owner != user {
+            return Err(VaultError::NotTicketOwner);
+        }
+        if !ticket.finalized {
+            return Err(VaultError::TicketNotFinalized);
+        }
+        // A freeze placed after the request still blocks the payout
+        if is_sxlm_frozen(&env, &user)? {
+            return Err(VaultError::AccountFrozen);
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::WithdrawTicket(ticket_id));
+        set_pending_withdrawals(&env, get_pending_withdrawals(&env) - ticket.xlm_amount);
+        set_finalized_withdrawals(&env, get_finalized_withdrawals(&env) - ticket.xlm_amount);
+
+        let xlm_client = token::Client::new(&env, &get_xlm_token(&env)?);
+        xlm_client.transfer(&env.current_contract_address(), &user, &ticket.xlm_amount);
+
+        env.events().publish(
+            (Symbol::new(&env, "withdraw_claimed"),),
+            (user, ticket_id, ticket.xlm_amount),
+        );
+
+        unlock_reentrancy(&env);
+        Ok(ticket.xlm_amount)
+    }
+
+    /// Get a queued withdrawal ticket
+    pub fn get_withdraw_ticket(env: Env, ticket_id: u64) -> Result<WithdrawTicket, VaultError> {
+        extend_instance_ttl(&env);
+        get_ticket(&env, ticket_id)
+    }
+
+    /// Get the oldest ticket id still waiting for liquidity
+    pub fn queue_head(env: Env) -> u64 {
+        extend_instance_ttl(&env);
+        get_queue_head(&env)
+    }
+
+    /// Get the id the next withdrawal request will receive
+    pub fn queue_tail(env: Env) -> u64 {
+        extend_instance_ttl(&env);
+        get_queue_tail(&env)
+    }
+
+    /// Get total XLM owed to unclaimed withdrawal tickets
+    pub fn get_pending_withdrawals(env: Env) -> i128 {
+        extend_instance_ttl(&env);
+        get_pending_withdrawals(&env)
+    }
+
+    /// Get current exchange rate (XLM per sXLM, scaled by PRECISION)
+    pub fn get_exchange_rate(env: Env) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        get_exchange_rate(&env)
+    }
+
+    /// Get total XLM managed by vault: idle XLM plus the value reported by
+    /// every strategy. Excludes XLM owed to queued withdrawals.
+    pub fn get_total_assets(env: Env) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        get_total_assets(&env)
+    }
+
+    /// Get idle XLM held by the vault (excludes queued withdrawals)
+    pub fn get_idle_assets(env: Env) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        get_available_liquidity(&env)
+    }
+
+    /// Get total deposits (without yield)
+    pub fn get_total_deposits(env: Env) -> i128 {
+        extend_instance_ttl(&env);
+        get_total_deposits(&env)
+    }
+
+    /// Get yield accrued
+    pub fn get_yield_accrued(env: Env) -> i128 {
+        extend_instance_ttl(&env);
+        get_yield_accrued(&env)
+    }
+
+    /// Get the XLM cost basis of `user`'s sXLM
+    pub fn get_user_deposit(env: Env, user: Address) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        Ok(get_position(&env, &user)?.cost_basis)
+    }
+
+    /// `user`'s sXLM, its current value, cost basis and PnL
+    pub fn position(env: Env, user: Address) -> Result<Position, VaultError> {
+        extend_instance_ttl(&env);
+        get_position_view(&env, &user)
+    }
+
+    /// Write a legacy depositor's position from their deposit counter and
+    /// current balance. Anyone may call it; it does nothing for other holders.
+    /// Vault operations sync legacy holders themselves, but transfers cannot,
+    /// since the hook runs inside the token and cannot read balances back.
+    pub fn sync_position(env: Env, user: Address) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        if is_unsynced_legacy_holder(&env, &user) {
+            let record = get_position(&env, &user)?;
+            set_position(&env, &user, &record);
+        }
+        Ok(())
+    }
+
+    /// Transfer hook called by the sXLM token after `amount` moves from
+    /// `from` to `to`. The receiver takes over the sender's average cost for
+    /// the moved sXLM, so a transfer realizes no PnL.
+    pub fn on_transfer(env: Env, from: Address, to: Address, amount: i128) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        // Positions are only written in the current layout, so transfers
+        // wait for `migrate` like every other state change
+        require_current_schema(&env)?;
+        get_sxlm_token(&env)?.require_auth();
+        // A legacy holder's position depends on a balance this hook cannot
+        // read mid-transfer, so it must be synced first
+        if is_unsynced_legacy_holder(&env, &from) || is_unsynced_legacy_holder(&env, &to) {
+            return Err(VaultError::PositionNotSynced);
+        }
+
+        let cost = remove_from_position(&env, &from, amount)?;
+        add_to_position(&env, &to, amount, cost)
+    }
+
+    /// Add yield to vault, paid in by the caller (keeper - simulates strategy returns)
+    pub fn add_yield(env: Env, caller: Address, amount: i128) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Keeper)?;
+        require_not_paused(&env, Operation::Yield)?;
+
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        accrue_management_fee(&env)?;
+
+        // Transfer XLM from caller to vault
+        let xlm_token = get_xlm_token(&env)?;
+        let xlm_client = token::Client::new(&env, &xlm_token);
+        xlm_client.transfer(&caller, &env.current_contract_address(), &amount);
+
+        let current_yield = get_yield_accrued(&env);
+        set_yield_accrued(&env, current_yield.checked_add(amount).ok_or(VaultError::Overflow)?);
+
+        // The net yield vests linearly so it cannot be captured by a deposit
+        // placed just before this call; the performance fee is taken up front
+        let performance_fee = bps_of(amount, get_performance_fee_bps(&env))?;
+        let locked = get_locked_yield(&env)
+            .checked_add(amount - performance_fee)
+            .ok_or(VaultError::Overflow)?;
+        start_vesting(&env, locked, get_vesting_period(&env));
+
+        // The fee is unlocked now, so its shares are priced at the current
+        // rate, leaving the rate unchanged by this call. From here on the
+        // treasury earns its share of the vesting yield like any holder.
+        let total_assets = get_total_assets(&env)?;
+        mint_fee_shares(&env, performance_fee, total_assets, "performance")?;
+
+        env.events().publish(
+            (Symbol::new(&env, "yield_added"),),
+            amount,
+        );
+        Ok(())
+    }
+
+    // ============ Fees ============
+
+    /// Set the address that receives fee shares (fee manager)
+    pub fn set_treasury(env: Env, caller: Address, treasury: Address) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::FeeManager)?;
+
+        // Fees accrued so far belong to the previous treasury
+        accrue_management_fee(&env)?;
+        env.storage().instance().set(&DataKey::Treasury, &treasury);
+
+        env.events().publish(
+            (Symbol::new(&env, "treasury_set"),),
+            treasury,
+        );
+        Ok(())
+    }
+
+    // Fee rates are changed through the timelock (`schedule`)
+
+    /// Get fee settings and the management fee accrued but not yet minted
+    pub fn preview_fees(env: Env) -> Result<FeePreview, VaultError> {
+        extend_instance_ttl(&env);
+        get_fee_preview(&env, get_total_assets(&env)?)
+    }
+
+    // ============ Yield Vesting ============
+
+    /// Set the number of ledgers over which added yield unlocks (owner only).
+    /// Yield that is still locked restarts vesting over the new period.
+    pub fn set_vesting_period(env: Env, ledgers: u32) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        if ledgers > MAX_VESTING_PERIOD {
+            return Err(VaultError::InvalidVestingPeriod);
+        }
+
+        start_vesting(&env, get_locked_yield(&env), ledgers);
+        env.storage().instance().set(&DataKey::VestingPeriod, &ledgers);
+
+        env.events().publish(
+            (Symbol::new(&env, "vesting_period_set"),),
+            ledgers,
+        );
+        Ok(())
+    }
+
+    /// Get yield added via `add_yield` that has not unlocked yet
+    pub fn locked_yield(env: Env) -> i128 {
+        extend_instance_ttl(&env);
+        get_locked_yield(&env)
+    }
+
+    /// Get the ledger at which all currently locked yield is unlocked
+    pub fn vesting_end(env: Env) -> u32 {
+        extend_instance_ttl(&env);
+        get_vesting_end(&env)
+    }
+
+    /// Get the vesting period in ledgers
+    pub fn vesting_period(env: Env) -> u32 {
+        extend_instance_ttl(&env);
+        get_vesting_period(&env)
+    }
+
+    /// Pause deposits, withdrawals, yield and strategy movements (guardian).
+    /// sXLM transfers are left running. Allowed while a migration is pending.
+    pub fn pause(env: Env, caller: Address) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_role(&env, &caller, Role::Guardian)?;
+        for operation in VAULT_OPERATIONS {
+            set_pause_flag(&env, operation, true)?;
+        }
+        Ok(())
+    }
+
+    /// Clear the pause flags set by `pause` (guardian)
+    pub fn unpause(env: Env, caller: Address) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Guardian)?;
+        for operation in VAULT_OPERATIONS {
+            set_pause_flag(&env, operation, false)?;
+        }
+        Ok(())
+    }
+
+    /// Pause or resume a single operation (guardian). Pausing is allowed
+    /// while a migration is pending.
+    pub fn set_paused(
+        env: Env,
+        caller: Address,
+        operation: Operation,
+        paused: bool,
+    ) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        if !paused {
+            require_current_schema(&env)?;
+        }
+        require_role(&env, &caller, Role::Guardian)?;
+        set_pause_flag(&env, operation, paused)
+    }
+
+    /// Enter or leave withdraw-only emergency mode (guardian). While enabled,
+    /// deposits, yield and strategy deployment are blocked and redemptions
+    /// pay each share its pro-rata slice of idle XLM and of every strategy.
+    pub fn set_withdraw_only(env: Env, caller: Address, enabled: bool) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        if !enabled {
+            require_current_schema(&env)?;
+        }
+        require_role(&env, &caller, Role::Guardian)?;
+
+        env.storage().instance().set(&DataKey::WithdrawOnly, &enabled);
+        env.events().publish(
+            (Symbol::new(&env, "withdraw_only"),),
+            enabled,
+        );
+        Ok(())
+    }
+
+    /// Permanently shut the vault down (guardian). Recalls everything each
+    /// strategy will return, records what it could not as a per-strategy
+    /// loss, unlocks any vesting yield and freezes the redemption rate at the
+    /// recovered XLM per sXLM. Afterwards holders exit via `emergency_redeem`
+    /// and queued tickets can still be processed and claimed.
+    /// Allowed while a migration is pending.
+    pub fn emergency_shutdown(env: Env, caller: Address) -> Result<ShutdownState, VaultError> {
+        extend_instance_ttl(&env);
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        require_role(&env, &caller, Role::Guardian)?;
+        if is_shutdown(&env) {
+            return Err(VaultError::VaultShutdown);
+        }
+
+        let mut total_loss: i128 = 0;
+        for strategy in get_strategies(&env).iter() {
+            let (reported, recovered) = recover_strategy(&env, &strategy);
+            let loss = reported - recovered;
+            if loss > 0 {
+                let key = DataKey::StrategyLoss(strategy.clone());
+                env.storage().persistent().set(&key, &loss);
+                extend_persistent_ttl(&env, &key);
+                total_loss = total_loss.checked_add(loss).ok_or(VaultError::Overflow)?;
+                env.events().publish(
+                    (Symbol::new(&env, "strategy_loss"), strategy.clone()),
+                    (reported, recovered, loss),
+                );
+            }
+            env.storage()
+                .instance()
+                .remove(&DataKey::Strategy(strategy.clone()));
+        }
+        set_strategies(&env, &Vec::new(&env));
+
+        // Everything recovered is distributed, including yield still vesting
+        start_vesting(&env, 0, 0);
+        accrue_management_fee(&env)?;
+
+        let state = ShutdownState {
+            assets: get_available_liquidity(&env)?,
+            supply: get_sxlm_supply(&env)?,
+            total_loss,
+            ledger: env.ledger().sequence(),
+        };
+        env.storage().instance().set(&DataKey::Shutdown, &state);
+
+        env.events().publish(
+            (Symbol::new(&env, "shutdown"), caller),
+            (state.assets, state.supply, total_loss),
         );
 
-        unlock_reentrancy(&env);
-        xlm_to_return
+        unlock_reentrancy(&env);
+        Ok(state)
+    }
+
+    /// Burn `shares` sXLM after shutdown for their slice of the recovered XLM
+    /// at the frozen rate. Returns the XLM paid.
+    pub fn emergency_redeem(env: Env, user: Address, shares: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+
+        user.require_auth();
+
+        let state = get_shutdown(&env).ok_or(VaultError::NotShutdown)?;
+        if shares <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if get_redeemable_shares(&env, &user)? < shares {
+            return Err(VaultError::InsufficientBalance);
+        }
+
+        let xlm_to_return = mul_div(shares, state.assets, state.supply, Rounding::Floor)?;
+        if xlm_to_return == 0 {
+            return Err(VaultError::WithdrawalTooSmall);
+        }
+
+        settle_withdraw(&env, None, &user, &user, shares, xlm_to_return)?;
+
+        unlock_reentrancy(&env);
+        Ok(xlm_to_return)
+    }
+
+    /// XLM `emergency_redeem` would pay for `shares` (0 before shutdown)
+    pub fn preview_emergency_redeem(env: Env, shares: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        match get_shutdown(&env) {
+            Some(state) => mul_div(shares, state.assets, state.supply, Rounding::Floor),
+            None => Ok(0),
+        }
+    }
+
+    /// The frozen shutdown snapshot, if the vault has been shut down
+    pub fn shutdown_state(env: Env) -> Option<ShutdownState> {
+        extend_instance_ttl(&env);
+        get_shutdown(&env)
+    }
+
+    /// XLM `strategy` failed to return at shutdown
+    pub fn strategy_loss(env: Env, strategy: Address) -> i128 {
+        extend_instance_ttl(&env);
+        get_strategy_loss(&env, &strategy)
+    }
+
+    /// Get every pause flag
+    pub fn pause_status(env: Env) -> Result<PauseStatus, VaultError> {
+        extend_instance_ttl(&env);
+        get_pause_status(&env)
+    }
+
+    /// Get admin address
+    pub fn admin(env: Env) -> Result<Address, VaultError> {
+        extend_instance_ttl(&env);
+        get_admin(&env)
+    }
+
+    /// Get XLM token address
+    pub fn xlm_token(env: Env) -> Result<Address, VaultError> {
+        extend_instance_ttl(&env);
+        get_xlm_token(&env)
+    }
+
+    /// Get sXLM token address
+    pub fn sxlm_token(env: Env) -> Result<Address, VaultError> {
+        extend_instance_ttl(&env);
+        get_sxlm_token(&env)
     }
 
-    /// Withdraw with slippage protection
-    pub fn withdraw_with_min_out(
-        env: Env,
-        user: Address,
-        sxlm_amount: i128,
-        min_xlm_out: i128,
-    ) -> i128 {
-        let xlm_received = Self::withdraw(env.clone(), user, sxlm_amount);
+    /// Check if any vault operation is paused, or the vault is in withdraw-only
+    /// mode or shut down
+    pub fn is_paused(env: Env) -> bool {
+        extend_instance_ttl(&env);
+        is_shutdown(&env)
+            || is_withdraw_only(&env)
+            || VAULT_OPERATIONS
+                .iter()
+                .any(|operation| get_pause_flag(&env, *operation))
+    }
 
-        if xlm_received < min_xlm_out {
-            panic!("Slippage: received {} but expected at least {}", xlm_received, min_xlm_out);
+    /// Get maximum single deposit
+    pub fn max_deposit_per_tx(env: Env) -> i128 {
+        extend_instance_ttl(&env);
+        get_max_deposit(&env)
+    }
+
+    /// Maximum XLM `user` can deposit right now (0 while paused)
+    pub fn max_deposit(env: Env, user: Address) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        max_deposit_for(&env, &user)
+    }
+
+    /// Maximum XLM `user` can withdraw instantly right now (0 while paused
+    /// or in withdraw-only mode, where only share redemptions are allowed)
+    pub fn max_withdraw(env: Env, user: Address) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        max_withdraw_for(&env, &user)
+    }
+
+    /// Get total TVL cap
+    pub fn total_cap(env: Env) -> i128 {
+        extend_instance_ttl(&env);
+        get_total_cap(&env)
+    }
+
+    /// Snapshot of vault-wide state for dashboards and monitoring, replacing
+    /// a round of separate view calls
+    pub fn vault_state(env: Env) -> Result<VaultState, VaultError> {
+        extend_instance_ttl(&env);
+
+        let mut strategies = Vec::new(&env);
+        for strategy in get_strategies(&env).iter() {
+            let config = get_strategy_config(&env, &strategy)?;
+            let deployed = StrategyClient::new(&env, &strategy).balance();
+            strategies.push_back(StrategyState {
+                strategy,
+                weight_bps: config.weight_bps,
+                debt_limit: config.debt_limit,
+                deployed,
+            });
         }
 
-        xlm_received
+        let total_assets = get_total_assets(&env)?;
+        Ok(VaultState {
+            total_assets,
+            idle_assets: get_available_liquidity(&env)?,
+            strategies,
+            total_supply: get_sxlm_supply(&env)?,
+            exchange_rate: get_exchange_rate(&env)?,
+            locked_yield: get_locked_yield(&env),
+            pause: get_pause_status(&env)?,
+            max_deposit_per_tx: get_max_deposit(&env),
+            total_cap: get_total_cap(&env),
+            total_deposits: get_total_deposits(&env),
+            default_user_cap: get_default_user_cap(&env),
+            fees: get_fee_preview(&env, total_assets)?,
+            pending_withdrawals: get_pending_withdrawals(&env),
+            withdraw_capacity: get_withdraw_capacity(&env)?,
+            schema_version: get_schema_version(&env),
+        })
     }
 
-    /// Get current exchange rate (XLM per sXLM, scaled by PRECISION)
-    pub fn get_exchange_rate(env: Env) -> i128 {
+    /// Snapshot of `user`'s position and limits
+    pub fn user_state(env: Env, user: Address) -> Result<UserState, VaultError> {
         extend_instance_ttl(&env);
+        let xlm_client = token::Client::new(&env, &get_xlm_token(&env)?);
+        Ok(UserState {
+            position: get_position_view(&env, &user)?,
+            xlm_balance: xlm_client.balance(&user),
+            max_deposit: max_deposit_for(&env, &user)?,
+            max_withdraw: max_withdraw_for(&env, &user)?,
+            deposit_limit: deposit_headroom(&env, &user)?,
+            allowlisted: is_allowlisted(&env, &user),
+        })
+    }
 
-        let sxlm_token = get_sxlm_token(&env);
-        let total_sxlm: i128 = env.invoke_contract(
-            &sxlm_token,
-            &Symbol::new(&env, "total_supply"),
-            ().into_val(&env),
+    /// Calculate how much sXLM user would receive for XLM deposit
+    pub fn preview_deposit(env: Env, xlm_amount: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        convert_to_shares(&env, xlm_amount, Rounding::Floor)
+    }
+
+    /// Calculate how much XLM user would receive for sXLM withdrawal
+    pub fn preview_withdraw(env: Env, sxlm_amount: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        convert_to_assets(&env, sxlm_amount, Rounding::Floor)
+    }
+
+    /// Calculate how much XLM `mint` would charge for `shares` sXLM
+    pub fn preview_mint(env: Env, shares: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        convert_to_assets(&env, shares, Rounding::Ceil)
+    }
+
+    /// Calculate how much XLM redeeming `shares` sXLM would return
+    pub fn preview_redeem(env: Env, shares: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        convert_to_assets(&env, shares, Rounding::Floor)
+    }
+
+    /// Calculate how much sXLM `withdraw_assets` would burn for `xlm_amount`
+    pub fn preview_withdraw_assets(env: Env, xlm_amount: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        convert_to_shares(&env, xlm_amount, Rounding::Ceil)
+    }
+
+    /// sXLM worth `assets` XLM at the current rate, rounded down
+    pub fn convert_to_shares(env: Env, assets: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        convert_to_shares(&env, assets, Rounding::Floor)
+    }
+
+    /// XLM worth `shares` sXLM at the current rate, rounded down
+    pub fn convert_to_assets(env: Env, shares: i128) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        convert_to_assets(&env, shares, Rounding::Floor)
+    }
+
+    // ============ Per-Address Limits ============
+
+    /// Set the cap on each address's position value, in XLM, for addresses
+    /// without an override (strategist)
+    pub fn set_default_user_cap(env: Env, caller: Address, cap: i128) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+        if cap < 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+
+        env.storage().instance().set(&DataKey::DefaultUserCap, &cap);
+        env.events().publish(
+            (Symbol::new(&env, "default_user_cap_set"),),
+            cap,
         );
+        Ok(())
+    }
 
-        if total_sxlm == 0 {
-            return PRECISION; // 1:1 initially
+    /// Override the position cap for each of `users`, or clear their
+    /// overrides with `None` (strategist)
+    pub fn set_user_caps(
+        env: Env,
+        caller: Address,
+        users: Vec<Address>,
+        cap: Option<i128>,
+    ) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+        if cap.is_some_and(|cap| cap < 0) {
+            return Err(VaultError::InvalidAmount);
         }
 
-        let total_assets = Self::get_total_assets(env);
-        total_assets
-            .checked_mul(PRECISION)
-            .expect("Overflow")
-            .checked_div(total_sxlm)
-            .expect("Division error")
+        for user in users.iter() {
+            let key = DataKey::UserCap(user.clone());
+            match cap {
+                Some(cap) => {
+                    env.storage().persistent().set(&key, &cap);
+                    extend_persistent_ttl(&env, &key);
+                }
+                None => env.storage().persistent().remove(&key),
+            }
+            env.events().publish(
+                (Symbol::new(&env, "user_cap_set"), user),
+                cap,
+            );
+        }
+        Ok(())
     }
 
-    /// Get total XLM managed by vault (deposits + yield)
-    pub fn get_total_assets(env: Env) -> i128 {
+    /// Turn allowlist mode on or off (strategist). While on, only
+    /// allowlisted addresses can receive deposits.
+    pub fn set_allowlist_enabled(env: Env, caller: Address, enabled: bool) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        let deposits = get_total_deposits(&env);
-        let yield_accrued = get_yield_accrued(&env);
-        deposits.checked_add(yield_accrued).expect("Overflow")
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+
+        env.storage().instance().set(&DataKey::AllowlistEnabled, &enabled);
+        env.events().publish(
+            (Symbol::new(&env, "allowlist_enabled"),),
+            enabled,
+        );
+        Ok(())
     }
 
-    /// Get total deposits (without yield)
-    pub fn get_total_deposits(env: Env) -> i128 {
+    /// Add `users` to the deposit allowlist (strategist)
+    pub fn add_to_allowlist(env: Env, caller: Address, users: Vec<Address>) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        get_total_deposits(&env)
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+
+        for user in users.iter() {
+            let key = DataKey::Allowlisted(user.clone());
+            env.storage().persistent().set(&key, &true);
+            extend_persistent_ttl(&env, &key);
+            env.events().publish(
+                (Symbol::new(&env, "allowlisted"), user),
+                true,
+            );
+        }
+        Ok(())
     }
 
-    /// Get yield accrued
-    pub fn get_yield_accrued(env: Env) -> i128 {
+    /// Remove `users` from the deposit allowlist (strategist). Existing
+    /// positions are untouched and can still be withdrawn.
+    pub fn remove_from_allowlist(
+        env: Env,
+        caller: Address,
+        users: Vec<Address>,
+    ) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        get_yield_accrued(&env)
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+
+        for user in users.iter() {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::Allowlisted(user.clone()));
+            env.events().publish(
+                (Symbol::new(&env, "allowlisted"), user),
+                false,
+            );
+        }
+        Ok(())
     }
 
-    /// Get user's total deposit
-    pub fn get_user_deposit(env: Env, user: Address) -> i128 {
+    /// XLM `user` may still deposit under the allowlist and their position
+    /// cap (0 if not allowlisted while allowlist mode is on). Vault-wide
+    /// limits are reflected in `max_deposit`.
+    pub fn deposit_limit(env: Env, user: Address) -> Result<i128, VaultError> {
         extend_instance_ttl(&env);
-        get_user_deposit(&env, &user)
+        deposit_headroom(&env, &user)
     }
 
-    /// Add yield to vault (admin only - simulates strategy returns)
-    pub fn add_yield(env: Env, amount: i128) {
+    /// Position cap that applies to `user`
+    pub fn user_cap(env: Env, user: Address) -> i128 {
         extend_instance_ttl(&env);
+        get_user_cap(&env, &user)
+    }
 
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+    /// Whether `user` is on the deposit allowlist
+    pub fn is_allowlisted(env: Env, user: Address) -> bool {
+        extend_instance_ttl(&env);
+        is_allowlisted(&env, &user)
+    }
+
+    /// Whether allowlist mode is on
+    pub fn allowlist_enabled(env: Env) -> bool {
+        extend_instance_ttl(&env);
+        is_allowlist_enabled(&env)
+    }
+
+    // ============ Outflow Limit ============
+
+    /// Cap instant withdrawals per rolling window of `window_ledgers`, as an
+    /// absolute XLM amount and/or bps of total assets, whichever is lower
+    /// (strategist). Withdrawals over the limit are queued instead. Passing
+    /// neither limit removes it.
+    pub fn set_outflow_limit(
+        env: Env,
+        caller: Address,
+        window_ledgers: u32,
+        max_amount: Option<i128>,
+        max_bps: Option<u32>,
+    ) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+
+        if max_amount.is_none() && max_bps.is_none() {
+            env.storage().instance().remove(&DataKey::OutflowLimit);
+            env.storage().instance().remove(&DataKey::OutflowUsed);
+            env.storage().instance().remove(&DataKey::OutflowUpdated);
+            env.events().publish(
+                (Symbol::new(&env, "outflow_limit_set"),),
+                None::<OutflowLimit>,
+            );
+            return Ok(());
+        }
+        if window_ledgers == 0
+            || max_amount.is_some_and(|amount| amount < 0)
+            || max_bps.is_some_and(|bps| bps > BPS_DENOMINATOR)
+        {
+            return Err(VaultError::InvalidOutflowLimit);
+        }
+
+        // Carry outflow already counted into the new window
+        if let Some(current) = get_outflow_limit(&env) {
+            let used = get_outflow_used(&env, current.window_ledgers);
+            env.storage().instance().set(&DataKey::OutflowUsed, &used);
+            env.storage()
+                .instance()
+                .set(&DataKey::OutflowUpdated, &env.ledger().sequence());
+        }
+
+        let limit = OutflowLimit {
+            window_ledgers,
+            max_amount,
+            max_bps,
+        };
+        env.storage().instance().set(&DataKey::OutflowLimit, &limit);
+        env.events().publish(
+            (Symbol::new(&env, "outflow_limit_set"),),
+            Some(limit),
+        );
+        Ok(())
+    }
+
+    /// Current outflow limit, if any
+    pub fn outflow_limit(env: Env) -> Option<OutflowLimit> {
+        extend_instance_ttl(&env);
+        get_outflow_limit(&env)
+    }
+
+    /// XLM that can be withdrawn instantly right now: the lower of idle
+    /// liquidity and what the outflow limit still allows this window
+    pub fn withdraw_capacity(env: Env) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        get_withdraw_capacity(&env)
+    }
+
+    // ============ Strategies ============
+
+    /// Register a yield strategy with a target weight (strategist)
+    pub fn add_strategy(
+        env: Env,
+        caller: Address,
+        strategy: Address,
+        weight_bps: u32,
+    ) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+        if is_shutdown(&env) {
+            return Err(VaultError::VaultShutdown);
+        }
+
+        let mut strategies = get_strategies(&env);
+        if strategies.contains(&strategy) {
+            return Err(VaultError::StrategyExists);
+        }
+        if strategies.len() >= MAX_STRATEGIES {
+            return Err(VaultError::TooManyStrategies);
+        }
+        if total_weight_with(&env, &strategy, weight_bps)? > BPS_DENOMINATOR {
+            return Err(VaultError::InvalidAllocation);
+        }
+
+        strategies.push_back(strategy.clone());
+        set_strategies(&env, &strategies);
+        set_strategy_config(
+            &env,
+            &strategy,
+            &StrategyConfig {
+                weight_bps,
+                debt_limit: i128::MAX,
+            },
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "strategy_added"), strategy),
+            weight_bps,
+        );
+        Ok(())
+    }
+
+    /// Recall all funds from a strategy and unregister it (strategist)
+    pub fn remove_strategy(env: Env, caller: Address, strategy: Address) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+        // Recalls stay open in withdraw-only mode
+        if get_pause_flag(&env, Operation::Strategy) {
+            return Err(VaultError::Paused);
+        }
+
+        let mut strategies = get_strategies(&env);
+        let index = strategies
+            .first_index_of(&strategy)
+            .ok_or(VaultError::StrategyNotFound)?;
+
+        let client = StrategyClient::new(&env, &strategy);
+        let value = client.balance();
+        if value > 0 {
+            recall_from_strategy(&env, &strategy, value);
+        }
+        if client.balance() > 0 {
+            return Err(VaultError::StrategyNotEmpty);
+        }
 
+        strategies.remove(index);
+        set_strategies(&env, &strategies);
+        env.storage()
+            .instance()
+            .remove(&DataKey::Strategy(strategy.clone()));
+
+        env.events().publish(
+            (Symbol::new(&env, "strategy_removed"), strategy),
+            value,
+        );
+        Ok(())
+    }
+
+    /// Update a strategy's target weight (strategist).
+    /// The sum of all weights may not exceed 100%.
+    pub fn set_allocation(
+        env: Env,
+        caller: Address,
+        strategy: Address,
+        weight_bps: u32,
+    ) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+
+        let mut config = get_strategy_config(&env, &strategy)?;
+        if total_weight_with(&env, &strategy, weight_bps)? > BPS_DENOMINATOR {
+            return Err(VaultError::InvalidAllocation);
+        }
+
+        config.weight_bps = weight_bps;
+        set_strategy_config(&env, &strategy, &config);
+
+        env.events().publish(
+            (Symbol::new(&env, "allocation_set"), strategy),
+            weight_bps,
+        );
+        Ok(())
+    }
+
+    /// Move idle XLM into a registered strategy (strategist)
+    pub fn strategy_deposit(
+        env: Env,
+        caller: Address,
+        strategy: Address,
+        amount: i128,
+    ) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+        require_not_paused(&env, Operation::Strategy)?;
+
+        let config = get_strategy_config(&env, &strategy)?;
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(VaultError::InvalidAmount);
+        }
+        if get_available_liquidity(&env)? < amount {
+            return Err(VaultError::InsufficientLiquidity);
+        }
+        let deployed = StrategyClient::new(&env, &strategy).balance();
+        if deployed.checked_add(amount).ok_or(VaultError::Overflow)? > config.debt_limit {
+            return Err(VaultError::DebtLimitExceeded);
         }
 
-        // Transfer XLM from admin to vault
-        let xlm_token = get_xlm_token(&env);
-        let xlm_client = token::Client::new(&env, &xlm_token);
-        xlm_client.transfer(&admin, &env.current_contract_address(), &amount);
+        deploy_to_strategy(&env, &strategy, amount)
+    }
 
-        let current_yield = get_yield_accrued(&env);
-        set_yield_accrued(&env, current_yield.checked_add(amount).expect("Overflow"));
+    /// Pull XLM from a registered strategy back to idle (strategist)
+    pub fn strategy_withdraw(
+        env: Env,
+        caller: Address,
+        strategy: Address,
+        amount: i128,
+    ) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+        // Recalls stay open in withdraw-only mode
+        if get_pause_flag(&env, Operation::Strategy) {
+            return Err(VaultError::Paused);
+        }
+
+        get_strategy_config(&env, &strategy)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+
+        Ok(recall_from_strategy(&env, &strategy, amount))
+    }
+
+    /// Cap the XLM the vault may deploy to a strategy (strategist)
+    pub fn set_debt_limit(
+        env: Env,
+        caller: Address,
+        strategy: Address,
+        debt_limit: i128,
+    ) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+
+        if debt_limit < 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+
+        let mut config = get_strategy_config(&env, &strategy)?;
+        config.debt_limit = debt_limit;
+        set_strategy_config(&env, &strategy, &config);
 
         env.events().publish(
-            (Symbol::new(&env, "yield_added"),),
-            amount,
+            (Symbol::new(&env, "debt_limit_set"), strategy),
+            debt_limit,
         );
+        Ok(())
     }
 
-    /// Pause the vault (admin only)
-    pub fn pause(env: Env) {
+    /// Set the share of TVL kept idle for instant withdrawals (strategist).
+    /// Buffer plus all strategy weights may not exceed 100%.
+    pub fn set_idle_buffer(env: Env, caller: Address, buffer_bps: u32) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
-        env.storage().instance().set(&DataKey::Paused, &true);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Strategist)?;
+
+        let mut weights: u32 = 0;
+        for strategy in get_strategies(&env).iter() {
+            weights += get_strategy_config(&env, &strategy)?.weight_bps;
+        }
+        if buffer_bps + weights > BPS_DENOMINATOR {
+            return Err(VaultError::InvalidAllocation);
+        }
 
+        env.storage().instance().set(&DataKey::IdleBufferBps, &buffer_bps);
         env.events().publish(
-            (Symbol::new(&env, "paused"),),
-            true,
+            (Symbol::new(&env, "idle_buffer_set"),),
+            buffer_bps,
         );
+        Ok(())
     }
 
-    /// Unpause the vault (admin only)
-    pub fn unpause(env: Env) {
+    /// Move XLM between idle balance and strategies towards each strategy's
+    /// target weight, keeping the idle buffer and respecting debt limits.
+    /// Callable by the keeper.
+    pub fn rebalance(env: Env, caller: Address) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
-        env.storage().instance().set(&DataKey::Paused, &false);
+        require_current_schema(&env)?;
+        require_not_reentering(&env)?;
+        lock_reentrancy(&env);
+        require_role(&env, &caller, Role::Keeper)?;
+        require_not_paused(&env, Operation::Strategy)?;
+
+        let strategies = get_strategies(&env);
+        let tvl = get_total_assets(&env)?;
+
+        let mut before: Vec<i128> = Vec::new(&env);
+        let mut targets: Vec<i128> = Vec::new(&env);
+        for strategy in strategies.iter() {
+            let config = get_strategy_config(&env, &strategy)?;
+            before.push_back(StrategyClient::new(&env, &strategy).balance());
+            targets.push_back(bps_of(tvl, config.weight_bps)?.min(config.debt_limit));
+        }
+
+        // Pull excess out of over-allocated strategies first so the freed
+        // XLM can fund the under-allocated ones
+        for (i, strategy) in strategies.iter().enumerate() {
+            let (current, target) = (before.get_unchecked(i as u32), targets.get_unchecked(i as u32));
+            if current > target {
+                recall_from_strategy(&env, &strategy, current - target);
+            }
+        }
+
+        let buffer = bps_of(tvl, get_idle_buffer_bps(&env))?;
+        let mut spare = get_available_liquidity(&env)?.saturating_sub(buffer);
+        for (i, strategy) in strategies.iter().enumerate() {
+            let (current, target) = (before.get_unchecked(i as u32), targets.get_unchecked(i as u32));
+            let amount = (target - current).min(spare);
+            if amount > 0 {
+                spare -= deploy_to_strategy(&env, &strategy, amount)?;
+            }
+        }
+
+        let mut after: Vec<i128> = Vec::new(&env);
+        for strategy in strategies.iter() {
+            after.push_back(StrategyClient::new(&env, &strategy).balance());
+        }
+
+        env.events().publish(
+            (Symbol::new(&env, "rebalanced"),),
+            (strategies, before, after),
+        );
+
+        unlock_reentrancy(&env);
+        Ok(())
+    }
+
+    /// Get the idle liquidity buffer in basis points of TVL
+    pub fn idle_buffer(env: Env) -> u32 {
+        extend_instance_ttl(&env);
+        get_idle_buffer_bps(&env)
+    }
+
+    /// Harvest a strategy's rewards into its position (keeper)
+    pub fn harvest(env: Env, caller: Address, strategy: Address) -> Result<i128, VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        require_role(&env, &caller, Role::Keeper)?;
+        require_not_paused(&env, Operation::Yield)?;
+
+        get_strategy_config(&env, &strategy)?;
+        let harvested = StrategyClient::new(&env, &strategy).harvest();
 
         env.events().publish(
-            (Symbol::new(&env, "unpaused"),),
-            true,
+            (Symbol::new(&env, "harvested"), strategy),
+            harvested,
         );
+        Ok(harvested)
+    }
+
+    /// Get registered strategy addresses
+    pub fn strategies(env: Env) -> Vec<Address> {
+        extend_instance_ttl(&env);
+        get_strategies(&env)
+    }
+
+    /// Get a strategy's registry entry
+    pub fn strategy_config(env: Env, strategy: Address) -> Result<StrategyConfig, VaultError> {
+        extend_instance_ttl(&env);
+        get_strategy_config(&env, &strategy)
     }
 
-    /// Set maximum single deposit (admin only)
-    pub fn set_max_deposit(env: Env, max_deposit: i128) {
+    // ============ Access Control ============
+
+    /// Grant `role` to `account` (owner only)
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
         admin.require_auth();
 
-        if max_deposit < MIN_AMOUNT {
-            panic!("Max deposit too low");
+        if role == Role::Owner {
+            return Err(VaultError::InvalidRole);
         }
 
-        env.storage().instance().set(&DataKey::MaxDeposit, &max_deposit);
+        let key = DataKey::Role(role, account.clone());
+        env.storage().persistent().set(&key, &true);
+        extend_persistent_ttl(&env, &key);
+
+        env.events().publish(
+            (Symbol::new(&env, "role_granted"), role),
+            account,
+        );
+        Ok(())
     }
 
-    /// Set total TVL cap (admin only)
-    pub fn set_total_cap(env: Env, total_cap: i128) {
+    /// Revoke `role` from `account` (owner only)
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
         admin.require_auth();
 
-        env.storage().instance().set(&DataKey::TotalCap, &total_cap);
+        if role == Role::Owner {
+            return Err(VaultError::InvalidRole);
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Role(role, account.clone()));
+
+        env.events().publish(
+            (Symbol::new(&env, "role_revoked"), role),
+            account,
+        );
+        Ok(())
     }
 
-    /// Get admin address
-    pub fn admin(env: Env) -> Address {
+    /// Check whether `account` holds `role`. The owner holds every role.
+    pub fn has_role(env: Env, role: Role, account: Address) -> Result<bool, VaultError> {
         extend_instance_ttl(&env);
-        env.storage().instance().get(&DataKey::Admin).unwrap()
+        has_role(&env, role, &account)
     }
 
-    /// Get XLM token address
-    pub fn xlm_token(env: Env) -> Address {
+    // ============ Timelock ============
+
+    /// Queue a timelocked action, executable once the delay has passed.
+    /// Upgrades, admin proposals and delay changes need the owner, cap
+    /// changes the strategist and fee changes the fee manager.
+    /// Returns the operation id.
+    pub fn schedule(env: Env, caller: Address, action: TimelockAction) -> Result<u64, VaultError> {
         extend_instance_ttl(&env);
-        get_xlm_token(&env)
+        require_current_schema(&env)?;
+        require_role(&env, &caller, timelock_role(&action)?)?;
+
+        let id: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TimelockNextId)
+            .unwrap_or(0);
+        let eta = env
+            .ledger()
+            .timestamp()
+            .checked_add(get_timelock_delay(&env))
+            .ok_or(VaultError::Overflow)?;
+        let op = TimelockOp {
+            action: action.clone(),
+            proposer: caller,
+            eta,
+        };
+
+        let key = DataKey::TimelockOp(id);
+        env.storage().persistent().set(&key, &op);
+        extend_persistent_ttl(&env, &key);
+        let mut ops = get_timelock_ops(&env);
+        ops.push_back(id);
+        set_timelock_ops(&env, &ops);
+        env.storage().instance().set(&DataKey::TimelockNextId, &(id + 1));
+
+        env.events().publish(
+            (Symbol::new(&env, "timelock_scheduled"), id),
+            (action, eta),
+        );
+        Ok(id)
     }
 
-    /// Get sXLM token address
-    pub fn sxlm_token(env: Env) -> Address {
+    /// Execute a scheduled action whose delay has passed (anyone)
+    pub fn execute(env: Env, id: u64) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        get_sxlm_token(&env)
+        let op = get_timelock_op(&env, id)?;
+        // A follow-up upgrade must stay possible if a migration is broken
+        if !matches!(op.action, TimelockAction::Upgrade(_)) {
+            require_current_schema(&env)?;
+        }
+        if env.ledger().timestamp() < op.eta {
+            return Err(VaultError::TimelockNotReady);
+        }
+
+        remove_timelock_op(&env, id);
+        env.events().publish(
+            (Symbol::new(&env, "timelock_executed"), id),
+            op.action.clone(),
+        );
+        apply_timelock_action(&env, op.action)
     }
 
-    /// Check if vault is paused
-    pub fn is_paused(env: Env) -> bool {
+    /// Drop a scheduled action before it executes (guardian). Allowed while
+    /// a migration is pending.
+    pub fn cancel(env: Env, caller: Address, id: u64) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        is_paused(&env)
+        require_role(&env, &caller, Role::Guardian)?;
+        get_timelock_op(&env, id)?;
+
+        remove_timelock_op(&env, id);
+        env.events().publish(
+            (Symbol::new(&env, "timelock_cancelled"), id),
+            caller,
+        );
+        Ok(())
     }
 
-    /// Get maximum single deposit
-    pub fn max_deposit(env: Env) -> i128 {
+    /// Get a scheduled operation
+    pub fn timelock_op(env: Env, id: u64) -> Result<TimelockOp, VaultError> {
         extend_instance_ttl(&env);
-        get_max_deposit(&env)
+        get_timelock_op(&env, id)
     }
 
-    /// Get total TVL cap
-    pub fn total_cap(env: Env) -> i128 {
+    /// Get the ids of all scheduled, not yet executed operations
+    pub fn timelock_ops(env: Env) -> Vec<u64> {
         extend_instance_ttl(&env);
-        get_total_cap(&env)
+        get_timelock_ops(&env)
     }
 
-    /// Calculate how much sXLM user would receive for XLM deposit
-    pub fn preview_deposit(env: Env, xlm_amount: i128) -> i128 {
+    /// Get the timelock delay in seconds
+    pub fn timelock_delay(env: Env) -> u64 {
         extend_instance_ttl(&env);
-        let exchange_rate = Self::get_exchange_rate(env);
-        xlm_amount
-            .checked_mul(PRECISION)
-            .expect("Overflow")
-            .checked_div(exchange_rate)
-            .expect("Division error")
+        get_timelock_delay(&env)
     }
 
-    /// Calculate how much XLM user would receive for sXLM withdrawal
-    pub fn preview_withdraw(env: Env, sxlm_amount: i128) -> i128 {
+    // ============ Admin Transfer ============
+    // Proposing a new admin goes through the timelock
+    // (`TimelockAction::ProposeAdmin`); the new admin then accepts.
+
+    /// Accept a pending admin proposal (proposed admin only)
+    pub fn accept_admin(env: Env) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        let exchange_rate = Self::get_exchange_rate(env);
-        sxlm_amount
-            .checked_mul(exchange_rate)
-            .expect("Overflow")
-            .checked_div(PRECISION)
-            .expect("Division error")
-    }
+        require_current_schema(&env)?;
+        let pending: PendingTransfer = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .ok_or(VaultError::NoPendingTransfer)?;
+        pending.address.require_auth();
+
+        if let Some(expires_ledger) = pending.expires_ledger {
+            if env.ledger().sequence() > expires_ledger {
+                return Err(VaultError::TransferExpired);
+            }
+        }
 
-    /// Upgrade contract (admin only) - for future upgrades
-    pub fn upgrade(env: Env, new_wasm_hash: soroban_sdk::BytesN<32>) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+        let admin = get_admin(&env)?;
+        env.storage().instance().set(&DataKey::Admin, &pending.address);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
 
-        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        env.events().publish(
+            (Symbol::new(&env, "admin_transferred"),),
+            (admin, pending.address),
+        );
+        Ok(())
     }
 
-    /// Transfer admin (admin only)
-    pub fn transfer_admin(env: Env, new_admin: Address) {
+    /// Withdraw a pending admin proposal (owner only)
+    pub fn cancel_admin_transfer(env: Env) -> Result<(), VaultError> {
         extend_instance_ttl(&env);
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
         admin.require_auth();
 
-        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        if !env.storage().instance().has(&DataKey::PendingAdmin) {
+            return Err(VaultError::NoPendingTransfer);
+        }
+        env.storage().instance().remove(&DataKey::PendingAdmin);
 
         env.events().publish(
-            (Symbol::new(&env, "admin_transferred"),),
-            (admin, new_admin),
+            (Symbol::new(&env, "admin_transfer_cancelled"),),
+            admin,
+        );
+        Ok(())
+    }
+
+    /// Get the pending admin proposal, if any
+    pub fn pending_admin(env: Env) -> Option<PendingTransfer> {
+        extend_instance_ttl(&env);
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+
+    /// Accept a pending proposal making this vault the sXLM minter (owner
+    /// only). The token requires the new minter to sign, which for a
+    /// contract means invoking `accept_minter` itself.
+    pub fn accept_sxlm_minter(env: Env) -> Result<(), VaultError> {
+        extend_instance_ttl(&env);
+        require_current_schema(&env)?;
+        let admin = get_admin(&env)?;
+        admin.require_auth();
+
+        env.invoke_contract::<()>(
+            &get_sxlm_token(&env)?,
+            &Symbol::new(&env, "accept_minter"),
+            vec![&env],
         );
+        Ok(())
     }
 }
 
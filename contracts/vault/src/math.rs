@@ -0,0 +1,27 @@
+// Fixed-point helpers shared by every share/asset conversion.
+// Callers pick the rounding direction explicitly so that rounding always
+// favors the vault: shares minted and assets paid out round down, shares
+// burned and assets charged round up.
+
+use crate::VaultError;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    Floor,
+    Ceil,
+}
+
+/// `a * b / denominator` for non-negative inputs, rounded as requested.
+/// Returns `Overflow` if the product overflows or `denominator` is zero.
+pub fn mul_div(a: i128, b: i128, denominator: i128, rounding: Rounding) -> Result<i128, VaultError> {
+    if denominator == 0 {
+        return Err(VaultError::Overflow);
+    }
+    let product = a.checked_mul(b).ok_or(VaultError::Overflow)?;
+    let quotient = product / denominator;
+    if rounding == Rounding::Ceil && product % denominator != 0 {
+        quotient.checked_add(1).ok_or(VaultError::Overflow)
+    } else {
+        Ok(quotient)
+    }
+}
@@ -20,14 +20,238 @@
 //      --sxlm_amount 500000000
 
 use crate::*;
-use soroban_sdk::{testutils::Address as _, Address, Env};
+use soroban_sdk::{
+    contract, contractimpl,
+    testutils::{Address as _, Ledger},
+    token::StellarAssetClient,
+    Address, Env, String,
+};
+use sxlm_token::{SxlmToken, SxlmTokenClient};
+use ::yield_strategy::{
+    StrategyError, YieldStrategy as SoroswapStrategy, YieldStrategyClient as SoroswapStrategyClient,
+};
+
+/// Strategy that simply holds XLM. Tests simulate gains by minting XLM to it
+/// and losses by moving XLM out of it.
+#[contract]
+pub struct MockStrategy;
+
+#[contractimpl]
+impl MockStrategy {
+    pub fn __constructor(env: Env, xlm: Address) {
+        env.storage().instance().set(&0u32, &xlm);
+    }
+
+    pub fn deposit(env: Env, from: Address, amount: i128) -> i128 {
+        let xlm: Address = env.storage().instance().get(&0u32).unwrap();
+        token::Client::new(&env, &xlm).transfer(&from, &env.current_contract_address(), &amount);
+        amount
+    }
+
+    /// Make `amount` of the position unwithdrawable while still reporting it
+    pub fn freeze(env: Env, amount: i128) {
+        env.storage().instance().set(&1u32, &amount);
+    }
+
+    pub fn withdraw(env: Env, to: Address, amount: i128) -> i128 {
+        let xlm: Address = env.storage().instance().get(&0u32).unwrap();
+        let frozen: i128 = env.storage().instance().get(&1u32).unwrap_or(0);
+        let client = token::Client::new(&env, &xlm);
+        let liquid = client.balance(&env.current_contract_address()) - frozen;
+        let out = amount.min(liquid.max(0));
+        client.transfer(&env.current_contract_address(), &to, &out);
+        out
+    }
+
+    pub fn balance(env: Env) -> i128 {
+        let xlm: Address = env.storage().instance().get(&0u32).unwrap();
+        token::Client::new(&env, &xlm).balance(&env.current_contract_address())
+    }
+
+    pub fn harvest(_env: Env) -> i128 {
+        0
+    }
+}
+
+/// Soroswap router stand-in for the LP strategy: swaps at 1:1 and issues one
+/// LP unit per XLM/USDC pair added
+#[allow(clippy::too_many_arguments)]
+mod router {
+    use super::*;
+
+    #[contract]
+    pub struct MockRouter;
+
+    #[contractimpl]
+    impl MockRouter {
+        pub fn swap_ext(
+            env: Env,
+            amount_in: i128,
+            _min_out: i128,
+            path: Vec<Address>,
+            to: Address,
+            _deadline: u64,
+        ) -> Vec<i128> {
+            let router = env.current_contract_address();
+            token::Client::new(&env, &path.get(0).unwrap()).transfer_from(&router, &to, &router, &amount_in);
+            token::Client::new(&env, &path.get(1).unwrap()).transfer(&router, &to, &amount_in);
+            vec![&env, amount_in, amount_in]
+        }
+
+        pub fn add_liq(
+            env: Env,
+            token_a: Address,
+            token_b: Address,
+            amount_a: i128,
+            amount_b: i128,
+            _min_a: i128,
+            _min_b: i128,
+            to: Address,
+            _deadline: u64,
+        ) -> (i128, i128, i128) {
+            let router = env.current_contract_address();
+            token::Client::new(&env, &token_a).transfer_from(&router, &to, &router, &amount_a);
+            token::Client::new(&env, &token_b).transfer_from(&router, &to, &router, &amount_b);
+            (amount_a, amount_b, amount_a.min(amount_b))
+        }
+
+        pub fn rem_liq(
+            env: Env,
+            token_a: Address,
+            token_b: Address,
+            liquidity: i128,
+            _min_a: i128,
+            _min_b: i128,
+            to: Address,
+            _deadline: u64,
+        ) -> (i128, i128) {
+            let router = env.current_contract_address();
+            token::Client::new(&env, &token_a).transfer(&router, &to, &liquidity);
+            token::Client::new(&env, &token_b).transfer(&router, &to, &liquidity);
+            (liquidity, liquidity)
+        }
+    }
+}
+
+// Separate module: contract functions share a namespace per module
+mod short_router {
+    use super::*;
+
+    /// Router whose swaps answer with only the input amount
+    #[contract]
+    pub struct ShortRouter;
+
+    #[contractimpl]
+    impl ShortRouter {
+        pub fn swap_ext(
+            env: Env,
+            amount_in: i128,
+            _min_out: i128,
+            _path: Vec<Address>,
+            _to: Address,
+            _deadline: u64,
+        ) -> Vec<i128> {
+            vec![&env, amount_in]
+        }
+    }
+}
+use router::MockRouter;
+use short_router::ShortRouter;
+
+struct Setup<'a> {
+    env: Env,
+    admin: Address,
+    vault: VaultClient<'a>,
+    xlm: token::Client<'a>,
+    sxlm: SxlmTokenClient<'a>,
+}
+
+fn setup() -> Setup<'static> {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let xlm_id = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let sxlm_id = env.register(SxlmToken, ());
+    let vault_id = env.register(Vault, ());
+
+    let vault = VaultClient::new(&env, &vault_id);
+    let sxlm = SxlmTokenClient::new(&env, &sxlm_id);
+    sxlm.initialize(
+        &admin,
+        &vault_id,
+        &7,
+        &String::from_str(&env, "Staked XLM"),
+        &String::from_str(&env, "sXLM"),
+    );
+    vault.initialize(&admin, &xlm_id, &sxlm_id);
+    sxlm.set_transfer_hook(&vault_id, &Some(vault_id.clone()));
+
+    StellarAssetClient::new(&env, &xlm_id).mint(&admin, &(1_000_000 * PRECISION));
+    let xlm = token::Client::new(&env, &xlm_id);
+
+    Setup { env, admin, vault, xlm, sxlm }
+}
+
+fn add_mock_strategy(s: &Setup, weight_bps: u32) -> Address {
+    let strategy = s.env.register(MockStrategy, (s.xlm.address.clone(),));
+    s.vault.add_strategy(&s.admin, &strategy, &weight_bps);
+    strategy
+}
+
+/// Advance past the end of the current yield vesting schedule
+fn vest(s: &Setup) {
+    let end = s.vault.vesting_end();
+    s.env.ledger().with_mut(|l| l.sequence_number = end);
+}
+
+/// Virtual shares absorb a tiny slice of yield, so yield results are compared
+/// to within one millionth
+fn assert_close(actual: i128, expected: i128) {
+    let tolerance = expected.abs() / 1_000_000 + 10;
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{} not within {} of {}",
+        actual,
+        tolerance,
+        expected
+    );
+}
+
+fn funded_user(s: &Setup, amount: i128) -> Address {
+    let user = Address::generate(&s.env);
+    StellarAssetClient::new(&s.env, &s.xlm.address).mint(&user, &amount);
+    user
+}
+
+/// Schedule `action` as the owner, wait out the delay and execute it
+fn timelocked(s: &Setup, action: TimelockAction) {
+    let id = s.vault.schedule(&s.admin, &action);
+    let delay = s.vault.timelock_delay();
+    s.env.ledger().with_mut(|l| l.timestamp += delay);
+    s.vault.execute(&id);
+}
+
+/// Deterministic xorshift generator for property-style tests
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform-ish value in `[lo, hi]`
+    fn range(&mut self, lo: i128, hi: i128) -> i128 {
+        lo + (self.next() as i128).rem_euclid(hi - lo + 1)
+    }
+}
 
 #[test]
 fn test_data_key_variants() {
     // Test that DataKey enum variants are correctly defined
-    let env = Env::default();
-    let addr = Address::generate(&env);
-
     let _ = DataKey::Admin;
     let _ = DataKey::XlmToken;
     let _ = DataKey::SxlmToken;
@@ -42,3 +266,1720 @@ fn test_precision_constant() {
     // Verify precision is 7 decimals (1.0 = 10_000_000)
     assert_eq!(PRECISION, 1_0000000);
 }
+
+#[test]
+fn test_cannot_reinitialize() {
+    let s = setup();
+    assert_eq!(
+        s.vault.try_initialize(&s.admin, &s.xlm.address, &s.sxlm.address),
+        Err(Ok(VaultError::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn test_uninitialized_vault() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let vault = VaultClient::new(&env, &env.register(Vault, ()));
+    let user = Address::generate(&env);
+
+    assert_eq!(vault.try_admin(), Err(Ok(VaultError::NotInitialized)));
+    assert_eq!(vault.try_deposit(&user, &MIN_AMOUNT), Err(Ok(VaultError::NotInitialized)));
+}
+
+#[test]
+fn test_deposit_and_withdraw() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+
+    assert_eq!(s.vault.deposit(&user, &100_0000000), 100_0000000);
+    assert_eq!(s.sxlm.balance(&user), 100_0000000);
+    assert_eq!(s.vault.get_total_assets(), 100_0000000);
+
+    assert_eq!(s.vault.withdraw(&user, &40_0000000), 40_0000000);
+    assert_eq!(s.xlm.balance(&user), 40_0000000);
+    assert_eq!(s.sxlm.balance(&user), 60_0000000);
+}
+
+#[test]
+fn test_yield_raises_exchange_rate() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+
+    s.vault.deposit(&user, &100_0000000);
+    s.vault.add_yield(&s.admin, &10_0000000);
+    vest(&s);
+
+    assert_close(s.vault.get_exchange_rate(), 1_1000000);
+    assert_close(s.vault.withdraw(&user, &100_0000000), 110_0000000);
+}
+
+#[test]
+fn test_deposit_errors() {
+    let s = setup();
+    let user = funded_user(&s, 1000_0000000);
+
+    assert_eq!(
+        s.vault.try_deposit(&user, &(MIN_AMOUNT - 1)),
+        Err(Ok(VaultError::BelowMinimum))
+    );
+
+    timelocked(&s, TimelockAction::SetMaxDeposit(10_0000000));
+    assert_eq!(
+        s.vault.try_deposit(&user, &11_0000000),
+        Err(Ok(VaultError::AboveMaxDeposit))
+    );
+
+    timelocked(&s, TimelockAction::SetTotalCap(15_0000000));
+    s.vault.deposit(&user, &10_0000000);
+    assert_eq!(
+        s.vault.try_deposit(&user, &6_0000000),
+        Err(Ok(VaultError::CapExceeded))
+    );
+
+    s.vault.pause(&s.admin);
+    assert_eq!(s.vault.try_deposit(&user, &1_0000000), Err(Ok(VaultError::Paused)));
+    assert_eq!(s.vault.try_withdraw(&user, &1_0000000), Err(Ok(VaultError::Paused)));
+}
+
+#[test]
+fn test_withdraw_errors() {
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+    s.vault.deposit(&user, &10_0000000);
+
+    assert_eq!(
+        s.vault.try_withdraw(&user, &(MIN_AMOUNT - 1)),
+        Err(Ok(VaultError::BelowMinimum))
+    );
+    assert_eq!(
+        s.vault.try_withdraw(&user, &11_0000000),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+}
+
+#[test]
+fn test_frozen_holder_cannot_redeem() {
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+    let spender = Address::generate(&s.env);
+    s.vault.deposit(&user, &10_0000000);
+    s.sxlm.approve(&user, &spender, &5_0000000, &1000);
+
+    s.sxlm.freeze(&s.admin, &user);
+    assert_eq!(s.vault.max_withdraw(&user), 0);
+    assert_eq!(
+        s.vault.try_withdraw(&user, &1_0000000),
+        Err(Ok(VaultError::AccountFrozen))
+    );
+    assert_eq!(
+        s.vault.try_withdraw_assets(&user, &1_0000000),
+        Err(Ok(VaultError::AccountFrozen))
+    );
+    assert_eq!(
+        s.vault.try_request_withdraw(&user, &1_0000000),
+        Err(Ok(VaultError::AccountFrozen))
+    );
+    assert_eq!(
+        s.vault.try_redeem_from(&spender, &user, &spender, &1_0000000),
+        Err(Ok(VaultError::AccountFrozen))
+    );
+
+    s.sxlm.unfreeze(&s.admin, &user);
+    s.vault.withdraw(&user, &1_0000000);
+}
+
+#[test]
+fn test_frozen_receiver_cannot_be_paid() {
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+    let receiver = Address::generate(&s.env);
+    s.vault.deposit(&user, &10_0000000);
+
+    s.sxlm.freeze(&s.admin, &receiver);
+    assert_eq!(
+        s.vault.try_redeem_to(&user, &receiver, &1_0000000),
+        Err(Ok(VaultError::AccountFrozen))
+    );
+
+    s.sxlm.unfreeze(&s.admin, &receiver);
+    s.vault.redeem_to(&user, &receiver, &1_0000000);
+    assert_eq!(s.xlm.balance(&receiver), 1_0000000);
+}
+
+#[test]
+fn test_freeze_after_request_blocks_claim() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    let other = funded_user(&s, 10_0000000);
+    s.vault.deposit(&user, &100_0000000);
+    let strategy = add_mock_strategy(&s, 9_000);
+    s.vault.strategy_deposit(&s.admin, &strategy, &90_0000000);
+
+    let frozen_ticket = s.vault.request_withdraw(&user, &30_0000000);
+    s.vault.deposit(&other, &10_0000000);
+    let other_ticket = s.vault.request_withdraw(&other, &5_0000000);
+    s.sxlm.freeze(&s.admin, &user);
+
+    // The queue still moves in order past the frozen owner's ticket
+    s.vault.strategy_withdraw(&s.admin, &strategy, &90_0000000);
+    assert_eq!(s.vault.process_queue(&10), 2);
+    assert_eq!(
+        s.vault.try_claim_withdraw(&user, &frozen_ticket),
+        Err(Ok(VaultError::AccountFrozen))
+    );
+    assert_eq!(s.vault.claim_withdraw(&other, &other_ticket), 5_0000000);
+
+    // The XLM stays reserved for the owner until the freeze is lifted
+    assert_eq!(s.vault.get_pending_withdrawals(), 30_0000000);
+    s.sxlm.unfreeze(&s.admin, &user);
+    assert_eq!(s.vault.claim_withdraw(&user, &frozen_ticket), 30_0000000);
+}
+
+#[test]
+fn test_slippage_protection() {
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+
+    assert_eq!(
+        s.vault.try_deposit_with_min_out(&user, &5_0000000, &6_0000000),
+        Err(Ok(VaultError::Slippage))
+    );
+    s.vault.deposit_with_min_out(&user, &5_0000000, &5_0000000);
+
+    assert_eq!(
+        s.vault.try_withdraw_with_min_out(&user, &5_0000000, &6_0000000),
+        Err(Ok(VaultError::Slippage))
+    );
+}
+
+#[test]
+fn test_add_yield_rejects_non_positive() {
+    let s = setup();
+    assert_eq!(s.vault.try_add_yield(&s.admin, &0), Err(Ok(VaultError::InvalidAmount)));
+}
+
+#[test]
+fn test_withdraw_queue_lifecycle() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &100_0000000);
+
+    let strategy = add_mock_strategy(&s, 8_000);
+    s.vault.strategy_deposit(&s.admin, &strategy, &80_0000000);
+    assert_eq!(
+        s.vault.try_withdraw(&user, &30_0000000),
+        Err(Ok(VaultError::InsufficientLiquidity))
+    );
+
+    let ticket_id = s.vault.request_withdraw(&user, &30_0000000);
+    assert_eq!(ticket_id, 0);
+    assert_eq!(s.sxlm.balance(&user), 70_0000000);
+    assert_eq!(s.vault.get_pending_withdrawals(), 30_0000000);
+    assert_eq!(s.vault.get_total_assets(), 70_0000000);
+    assert_eq!(s.vault.get_exchange_rate(), PRECISION);
+
+    // Only 20 XLM idle: the ticket stays queued
+    assert_eq!(s.vault.process_queue(&10), 0);
+    assert_eq!(
+        s.vault.try_claim_withdraw(&user, &ticket_id),
+        Err(Ok(VaultError::TicketNotFinalized))
+    );
+
+    // Liquidity returns
+    s.vault.strategy_withdraw(&s.admin, &strategy, &80_0000000);
+    assert_eq!(s.vault.process_queue(&10), 1);
+    assert_eq!(s.vault.queue_head(), 1);
+    assert!(s.vault.get_withdraw_ticket(&ticket_id).finalized);
+
+    assert_eq!(s.vault.claim_withdraw(&user, &ticket_id), 30_0000000);
+    assert_eq!(s.xlm.balance(&user), 30_0000000);
+    assert_eq!(s.vault.get_pending_withdrawals(), 0);
+    assert_eq!(
+        s.vault.try_get_withdraw_ticket(&ticket_id),
+        Err(Ok(VaultError::TicketNotFound))
+    );
+}
+
+#[test]
+fn test_withdraw_queue_is_fifo() {
+    let s = setup();
+    let alice = funded_user(&s, 50_0000000);
+    let bob = funded_user(&s, 50_0000000);
+    s.vault.deposit(&alice, &50_0000000);
+    s.vault.deposit(&bob, &50_0000000);
+
+    let strategy = add_mock_strategy(&s, 10_000);
+    s.vault.strategy_deposit(&s.admin, &strategy, &100_0000000);
+    let alice_ticket = s.vault.request_withdraw(&alice, &40_0000000);
+    let bob_ticket = s.vault.request_withdraw(&bob, &10_0000000);
+
+    // 10 XLM back is enough for Bob, but Alice is ahead in the queue
+    s.vault.strategy_withdraw(&s.admin, &strategy, &10_0000000);
+    assert_eq!(s.vault.process_queue(&10), 0);
+
+    s.vault.strategy_withdraw(&s.admin, &strategy, &40_0000000);
+    assert_eq!(s.vault.process_queue(&1), 1);
+    assert_eq!(s.vault.process_queue(&1), 1);
+    assert_eq!(s.vault.queue_head(), 2);
+
+    assert_eq!(
+        s.vault.try_claim_withdraw(&alice, &bob_ticket),
+        Err(Ok(VaultError::NotTicketOwner))
+    );
+    s.vault.claim_withdraw(&bob, &bob_ticket);
+    s.vault.claim_withdraw(&alice, &alice_ticket);
+    assert_eq!(s.xlm.balance(&alice), 40_0000000);
+    assert_eq!(s.xlm.balance(&bob), 10_0000000);
+}
+
+#[test]
+fn test_queued_liquidity_not_available_for_instant_withdraw() {
+    let s = setup();
+    let alice = funded_user(&s, 50_0000000);
+    let bob = funded_user(&s, 50_0000000);
+    s.vault.deposit(&alice, &50_0000000);
+    s.vault.deposit(&bob, &50_0000000);
+
+    let strategy = add_mock_strategy(&s, 6_000);
+    s.vault.strategy_deposit(&s.admin, &strategy, &60_0000000);
+    s.vault.request_withdraw(&alice, &40_0000000);
+
+    // 40 XLM idle, all owed to Alice's ticket
+    assert_eq!(
+        s.vault.try_withdraw(&bob, &10_0000000),
+        Err(Ok(VaultError::InsufficientLiquidity))
+    );
+    assert_eq!(s.vault.process_queue(&10), 1);
+}
+
+#[test]
+fn test_strategy_registry() {
+    let s = setup();
+    let a = add_mock_strategy(&s, 4_000);
+    let b = add_mock_strategy(&s, 5_000);
+
+    assert_eq!(s.vault.strategies().len(), 2);
+    assert_eq!(s.vault.strategy_config(&a).weight_bps, 4_000);
+    assert_eq!(s.vault.try_add_strategy(&s.admin, &a, &0), Err(Ok(VaultError::StrategyExists)));
+
+    // Weights may not exceed 100%
+    assert_eq!(
+        s.vault.try_set_allocation(&s.admin, &a, &5_001),
+        Err(Ok(VaultError::InvalidAllocation))
+    );
+    s.vault.set_allocation(&s.admin, &a, &5_000);
+
+    let c = s.env.register(MockStrategy, (s.xlm.address.clone(),));
+    assert_eq!(
+        s.vault.try_add_strategy(&s.admin, &c, &1),
+        Err(Ok(VaultError::InvalidAllocation))
+    );
+    assert_eq!(
+        s.vault.try_set_allocation(&s.admin, &c, &0),
+        Err(Ok(VaultError::StrategyNotFound))
+    );
+
+    s.vault.remove_strategy(&s.admin, &b);
+    assert_eq!(s.vault.strategies().len(), 1);
+    assert_eq!(
+        s.vault.try_strategy_config(&b),
+        Err(Ok(VaultError::StrategyNotFound))
+    );
+}
+
+#[test]
+fn test_total_assets_include_strategy_value() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &100_0000000);
+
+    let strategy = add_mock_strategy(&s, 7_000);
+    s.vault.strategy_deposit(&s.admin, &strategy, &70_0000000);
+    assert_eq!(s.vault.get_idle_assets(), 30_0000000);
+    assert_eq!(s.vault.get_total_assets(), 100_0000000);
+
+    // Strategy earns 7 XLM
+    StellarAssetClient::new(&s.env, &s.xlm.address).mint(&strategy, &7_0000000);
+    assert_eq!(s.vault.get_total_assets(), 107_0000000);
+    assert_close(s.vault.get_exchange_rate(), 1_0700000);
+
+    // Removing the strategy recalls everything to idle
+    s.vault.remove_strategy(&s.admin, &strategy);
+    assert_eq!(s.vault.get_idle_assets(), 107_0000000);
+    assert_eq!(s.vault.get_total_assets(), 107_0000000);
+}
+
+#[test]
+fn test_soroswap_strategy_implements_interface() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &100_0000000);
+
+    let usdc = s.env.register_stellar_asset_contract_v2(s.admin.clone()).address();
+    let router = s.env.register(MockRouter, ());
+    StellarAssetClient::new(&s.env, &usdc).mint(&router, &1000_0000000);
+    let strategy = SoroswapStrategyClient::new(&s.env, &s.env.register(SoroswapStrategy, ()));
+    strategy.initialize(
+        &s.admin,
+        &s.vault.address,
+        &router,
+        &router,
+        &s.xlm.address,
+        &usdc,
+    );
+    s.vault.add_strategy(&s.admin, &strategy.address, &5_000);
+
+    // The vault drives the LP strategy through its own `StrategyClient`
+    s.vault.strategy_deposit(&s.admin, &strategy.address, &40_0000000);
+    assert_eq!(strategy.get_lp_balance(), 20_0000000);
+    // The position is reported at principal, so TVL holds only what
+    // withdrawals can recover
+    assert_eq!(StrategyClient::new(&s.env, &strategy.address).balance(), 40_0000000);
+    assert_eq!(s.vault.get_total_assets(), 100_0000000);
+    assert_eq!(s.vault.harvest(&s.admin, &strategy.address), 0);
+
+    // Half the value recalls half the LP position
+    let received = s.vault.strategy_withdraw(&s.admin, &strategy.address, &20_0000000);
+    assert_eq!(received, 20_0000000);
+    assert_eq!(strategy.get_lp_balance(), 10_0000000);
+    assert_eq!(s.vault.get_idle_assets(), 80_0000000);
+
+    // Only the vault can move funds
+    assert_eq!(
+        strategy.try_withdraw(&s.admin, &1),
+        Err(Ok(StrategyError::NotVault))
+    );
+}
+
+#[test]
+fn test_soroswap_strategy_rejects_short_router_response() {
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+    s.vault.deposit(&user, &10_0000000);
+
+    let usdc = s.env.register_stellar_asset_contract_v2(s.admin.clone()).address();
+    let router = s.env.register(ShortRouter, ());
+    let strategy = SoroswapStrategyClient::new(&s.env, &s.env.register(SoroswapStrategy, ()));
+    strategy.initialize(&s.admin, &s.vault.address, &router, &router, &s.xlm.address, &usdc);
+
+    assert_eq!(
+        strategy.try_deposit(&s.vault.address, &10_0000000),
+        Err(Ok(StrategyError::InvalidRouterResponse))
+    );
+}
+
+#[test]
+fn test_strategy_deposit_requires_idle_liquidity() {
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+    s.vault.deposit(&user, &10_0000000);
+    let strategy = add_mock_strategy(&s, 10_000);
+
+    assert_eq!(
+        s.vault.try_strategy_deposit(&s.admin, &strategy, &11_0000000),
+        Err(Ok(VaultError::InsufficientLiquidity))
+    );
+    assert_eq!(
+        s.vault.try_strategy_deposit(&s.admin, &strategy, &0),
+        Err(Ok(VaultError::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_rebalance_converges_to_targets() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &100_0000000);
+
+    let a = add_mock_strategy(&s, 5_000);
+    let b = add_mock_strategy(&s, 3_000);
+    s.vault.set_idle_buffer(&s.admin, &2_000);
+
+    s.vault.rebalance(&s.admin);
+    assert_eq!(s.xlm.balance(&a), 50_0000000);
+    assert_eq!(s.xlm.balance(&b), 30_0000000);
+    assert_eq!(s.vault.get_idle_assets(), 20_0000000);
+
+    // Shift weight from A to B: A is drained before B is topped up
+    s.vault.set_allocation(&s.admin, &a, &2_000);
+    s.vault.set_allocation(&s.admin, &b, &6_000);
+    s.vault.rebalance(&s.admin);
+    assert_eq!(s.xlm.balance(&a), 20_0000000);
+    assert_eq!(s.xlm.balance(&b), 60_0000000);
+    assert_eq!(s.vault.get_total_assets(), 100_0000000);
+}
+
+#[test]
+fn test_rebalance_respects_debt_limit_and_buffer() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &100_0000000);
+
+    let a = add_mock_strategy(&s, 9_000);
+    s.vault.set_debt_limit(&s.admin, &a, &25_0000000);
+    s.vault.rebalance(&s.admin);
+    assert_eq!(s.xlm.balance(&a), 25_0000000);
+
+    assert_eq!(
+        s.vault.try_strategy_deposit(&s.admin, &a, &1_0000000),
+        Err(Ok(VaultError::DebtLimitExceeded))
+    );
+
+    // Buffer plus weights may not exceed 100%
+    assert_eq!(
+        s.vault.try_set_idle_buffer(&s.admin, &1_001),
+        Err(Ok(VaultError::InvalidAllocation))
+    );
+}
+
+#[test]
+fn test_rebalance_keeper_role() {
+    let s = setup();
+    let keeper = Address::generate(&s.env);
+    let stranger = Address::generate(&s.env);
+
+    assert_eq!(
+        s.vault.try_rebalance(&keeper),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    s.vault.grant_role(&Role::Keeper, &keeper);
+    assert!(s.vault.has_role(&Role::Keeper, &keeper));
+
+    s.vault.rebalance(&keeper);
+    assert_eq!(
+        s.vault.try_rebalance(&stranger),
+        Err(Ok(VaultError::Unauthorized))
+    );
+}
+
+#[test]
+fn test_rebalance_emits_allocations() {
+    use soroban_sdk::{testutils::Events, vec, IntoVal};
+
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+    s.vault.deposit(&user, &10_0000000);
+    let a = add_mock_strategy(&s, 10_000);
+
+    s.vault.rebalance(&s.admin);
+    let (_, topics, data) = s.env.events().all().last().unwrap();
+    assert_eq!(topics, (Symbol::new(&s.env, "rebalanced"),).into_val(&s.env));
+    let (strategies, before, after): (Vec<Address>, Vec<i128>, Vec<i128>) =
+        data.into_val(&s.env);
+    assert_eq!(strategies, vec![&s.env, a]);
+    assert_eq!(before, vec![&s.env, 0]);
+    assert_eq!(after, vec![&s.env, 10_0000000]);
+}
+
+#[test]
+fn test_performance_fee_minted_to_treasury() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    let treasury = Address::generate(&s.env);
+    s.vault.deposit(&user, &100_0000000);
+
+    let id = s
+        .vault
+        .schedule(&s.admin, &TimelockAction::SetPerformanceFee(1_000));
+    s.env.ledger().with_mut(|l| l.timestamp += DEFAULT_TIMELOCK_DELAY);
+    assert_eq!(s.vault.try_execute(&id), Err(Ok(VaultError::TreasuryNotSet)));
+    s.vault.set_treasury(&s.admin, &treasury);
+    s.vault.execute(&id);
+    s.vault.add_yield(&s.admin, &10_0000000);
+
+    // 10% of the 10 XLM yield goes to the treasury as sXLM
+    assert_close(s.vault.preview_withdraw(&s.sxlm.balance(&treasury)), 1_0000000);
+    assert_close(s.vault.preview_withdraw(&s.sxlm.balance(&user)), 100_0000000);
+
+    // The other 9 XLM vests to every holder, the treasury included
+    vest(&s);
+    let treasury_value = s.vault.preview_withdraw(&s.sxlm.balance(&treasury));
+    let user_value = s.vault.preview_withdraw(&s.sxlm.balance(&user));
+    assert!(treasury_value > 1_0000000);
+    assert_close(treasury_value + user_value, 110_0000000);
+}
+
+#[test]
+fn test_performance_fee_does_not_move_rate() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &100_0000000);
+    s.vault.set_treasury(&s.admin, &Address::generate(&s.env));
+    timelocked(&s, TimelockAction::SetPerformanceFee(2_000));
+
+    // Neither the fee nor the vesting yield steps the rate at the call, so a
+    // deposit/redeem around `add_yield` has nothing to capture
+    let rate = s.vault.get_exchange_rate();
+    s.vault.add_yield(&s.admin, &10_0000000);
+    assert_eq!(s.vault.get_exchange_rate(), rate);
+
+    let attacker = funded_user(&s, 1000_0000000);
+    let shares = s.vault.deposit(&attacker, &1000_0000000);
+    s.vault.add_yield(&s.admin, &10_0000000);
+    s.vault.withdraw(&attacker, &shares);
+    assert!(s.xlm.balance(&attacker) <= 1000_0000000);
+}
+
+#[test]
+fn test_management_fee_accrues_over_time() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    let treasury = Address::generate(&s.env);
+    s.vault.deposit(&user, &100_0000000);
+
+    s.vault.set_treasury(&s.admin, &treasury);
+    timelocked(&s, TimelockAction::SetManagementFee(200));
+    let id = s
+        .vault
+        .schedule(&s.admin, &TimelockAction::SetManagementFee(100));
+    s.env.ledger().with_mut(|l| l.timestamp += SECONDS_PER_YEAR);
+
+    let preview = s.vault.preview_fees();
+    assert_eq!(preview.management_fee_bps, 200);
+    assert_eq!(preview.pending_management_fee, 2_0000000);
+
+    // Fee changes settle the accrued fee before changing the rate
+    s.vault.execute(&id);
+    assert_eq!(s.vault.preview_fees().pending_management_fee, 0);
+    let treasury_value = s.vault.preview_withdraw(&s.sxlm.balance(&treasury));
+    assert_close(treasury_value, 2_0000000);
+}
+
+#[test]
+fn test_fee_bounds() {
+    let s = setup();
+    s.vault.set_treasury(&s.admin, &Address::generate(&s.env));
+
+    assert_eq!(
+        s.vault.try_schedule(
+            &s.admin,
+            &TimelockAction::SetPerformanceFee(MAX_PERFORMANCE_FEE_BPS + 1)
+        ),
+        Err(Ok(VaultError::FeeTooHigh))
+    );
+    assert_eq!(
+        s.vault.try_schedule(
+            &s.admin,
+            &TimelockAction::SetManagementFee(MAX_MANAGEMENT_FEE_BPS + 1)
+        ),
+        Err(Ok(VaultError::FeeTooHigh))
+    );
+    timelocked(&s, TimelockAction::SetPerformanceFee(MAX_PERFORMANCE_FEE_BPS));
+    timelocked(&s, TimelockAction::SetManagementFee(MAX_MANAGEMENT_FEE_BPS));
+}
+
+#[test]
+fn test_yield_vests_linearly() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &100_0000000);
+
+    s.vault.add_yield(&s.admin, &10_0000000);
+    assert_eq!(s.vault.locked_yield(), 10_0000000);
+    assert_eq!(s.vault.vesting_end(), s.env.ledger().sequence() + DEFAULT_VESTING_PERIOD);
+    assert_eq!(s.vault.get_total_assets(), 100_0000000);
+
+    s.env.ledger().with_mut(|l| l.sequence_number += DEFAULT_VESTING_PERIOD / 2);
+    assert_eq!(s.vault.locked_yield(), 5_0000000);
+    assert_eq!(s.vault.get_total_assets(), 105_0000000);
+
+    // New yield stacks on what is still locked and restarts the window
+    s.vault.add_yield(&s.admin, &2_0000000);
+    assert_eq!(s.vault.locked_yield(), 7_0000000);
+
+    vest(&s);
+    assert_eq!(s.vault.locked_yield(), 0);
+    assert_eq!(s.vault.get_total_assets(), 112_0000000);
+}
+
+#[test]
+fn test_yield_sandwich_captures_nothing() {
+    let s = setup();
+    let holder = funded_user(&s, 100_0000000);
+    let attacker = funded_user(&s, 1000_0000000);
+    s.vault.deposit(&holder, &100_0000000);
+
+    // Attacker front-runs the yield bot and exits right after it
+    let shares = s.vault.deposit(&attacker, &1000_0000000);
+    s.vault.add_yield(&s.admin, &10_0000000);
+    s.vault.withdraw(&attacker, &shares);
+    assert_eq!(s.xlm.balance(&attacker), 1000_0000000);
+
+    // The full yield ends up with the long-term holder
+    vest(&s);
+    assert_close(s.vault.preview_withdraw(&s.sxlm.balance(&holder)), 110_0000000);
+}
+
+#[test]
+fn test_set_vesting_period() {
+    let s = setup();
+    assert_eq!(
+        s.vault.try_set_vesting_period(&(MAX_VESTING_PERIOD + 1)),
+        Err(Ok(VaultError::InvalidVestingPeriod))
+    );
+
+    // A zero period unlocks yield immediately
+    let user = funded_user(&s, 10_0000000);
+    s.vault.deposit(&user, &10_0000000);
+    s.vault.set_vesting_period(&0);
+    s.vault.add_yield(&s.admin, &1_0000000);
+    assert_eq!(s.vault.locked_yield(), 0);
+    assert_eq!(s.vault.get_total_assets(), 11_0000000);
+}
+
+#[test]
+fn test_inflation_attack_is_unprofitable() {
+    let s = setup();
+    let attacker = funded_user(&s, 1000_0000000 + MIN_AMOUNT);
+    let victim = funded_user(&s, 1500_0000000);
+
+    // Attacker becomes the only holder of a single stroop of sXLM...
+    s.vault.deposit(&attacker, &MIN_AMOUNT);
+    s.sxlm.burn_self(&attacker, &(MIN_AMOUNT - 1));
+    assert_eq!(s.sxlm.total_supply(), 1);
+
+    // ...then donates XLM straight to the vault to inflate the rate
+    s.xlm.transfer(&attacker, &s.vault.address, &1000_0000000);
+
+    // Without virtual offsets the victim would be minted floor(1500 / 1000.1)
+    // = 1 share and the attacker could redeem half of the 2500 XLM pool
+    let naive_shares = 1500_0000000 / (1000_0000000 + MIN_AMOUNT);
+    assert_eq!(naive_shares, 1);
+
+    let victim_shares = s.vault.deposit(&victim, &1500_0000000);
+    assert!(victim_shares > 1);
+
+    // The victim keeps nearly all of their deposit and the attacker's one
+    // stroop of sXLM is worth a fraction of the donation
+    let victim_value = s.vault.preview_withdraw(&victim_shares);
+    let attacker_value = s.vault.preview_withdraw(&1);
+    assert!(victim_value >= 1500_0000000 - 1500_0000000 / 1000);
+    assert!(attacker_value < 1000_0000000 / 100);
+}
+
+#[test]
+fn test_virtual_offsets_keep_initial_rate() {
+    let s = setup();
+    assert_eq!(s.vault.get_exchange_rate(), PRECISION);
+    assert_eq!(s.vault.preview_deposit(&10_0000000), 10_0000000);
+
+    let user = funded_user(&s, 10_0000000);
+    assert_eq!(s.vault.deposit(&user, &10_0000000), 10_0000000);
+    assert_eq!(s.vault.preview_withdraw(&10_0000000), 10_0000000);
+}
+
+#[test]
+fn test_mint_and_withdraw_assets_round_in_vault_favor() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &10_0000000);
+    s.vault.set_vesting_period(&0);
+    s.vault.add_yield(&s.admin, &3_3333333);
+
+    // At a non-integer rate the share-denominated paths round against the caller
+    assert!(s.vault.preview_mint(&1_0000000) > s.vault.preview_redeem(&1_0000000));
+    assert!(
+        s.vault.preview_withdraw_assets(&1_0000000) > s.vault.convert_to_shares(&1_0000000)
+    );
+
+    let cost = s.vault.preview_mint(&5_0000000);
+    let xlm_before = s.xlm.balance(&user);
+    assert_eq!(s.vault.mint(&user, &5_0000000), cost);
+    assert_eq!(s.xlm.balance(&user), xlm_before - cost);
+
+    let shares_before = s.sxlm.balance(&user);
+    let burn = s.vault.preview_withdraw_assets(&2_0000000);
+    assert_eq!(s.vault.withdraw_assets(&user, &2_0000000), burn);
+    assert_eq!(s.sxlm.balance(&user), shares_before - burn);
+    assert_eq!(s.xlm.balance(&user), xlm_before - cost + 2_0000000);
+
+    // Minting then redeeming the same shares never returns more than was paid
+    let redeemed = s.vault.withdraw(&user, &5_0000000);
+    assert!(redeemed <= cost);
+}
+
+#[test]
+fn test_mint_and_withdraw_assets_errors() {
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+    assert_eq!(s.vault.try_mint(&user, &0), Err(Ok(VaultError::InvalidAmount)));
+    assert_eq!(
+        s.vault.try_withdraw_assets(&user, &1_0000000),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+
+    timelocked(&s, TimelockAction::SetMaxDeposit(5_0000000));
+    assert_eq!(
+        s.vault.try_mint(&user, &6_0000000),
+        Err(Ok(VaultError::AboveMaxDeposit))
+    );
+}
+
+#[test]
+fn test_max_deposit_and_withdraw_views() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    assert_eq!(s.vault.max_deposit(&user), s.vault.max_deposit_per_tx());
+
+    timelocked(&s, TimelockAction::SetTotalCap(50_0000000));
+    s.vault.deposit(&user, &40_0000000);
+    assert_eq!(s.vault.max_deposit(&user), 10_0000000);
+    assert_eq!(s.vault.max_withdraw(&user), 40_0000000);
+
+    // Deployed funds are not instantly withdrawable
+    add_mock_strategy(&s, 5_000);
+    s.vault.rebalance(&s.admin);
+    assert_eq!(s.vault.max_withdraw(&user), 20_0000000);
+
+    s.vault.pause(&s.admin);
+    assert_eq!(s.vault.max_deposit(&user), 0);
+    assert_eq!(s.vault.max_withdraw(&user), 0);
+}
+
+#[test]
+fn test_deposit_for_and_redeem_to() {
+    let s = setup();
+    let router = funded_user(&s, 10_0000000);
+    let user = Address::generate(&s.env);
+    let cold_wallet = Address::generate(&s.env);
+
+    // The router pays, the user receives the sXLM and the deposit credit
+    let minted = s.vault.deposit_for(&router, &user, &10_0000000);
+    assert_eq!(s.xlm.balance(&router), 0);
+    assert_eq!(s.sxlm.balance(&router), 0);
+    assert_eq!(s.sxlm.balance(&user), minted);
+    assert_eq!(s.vault.get_user_deposit(&user), 10_0000000);
+
+    let out = s.vault.redeem_to(&user, &cold_wallet, &4_0000000);
+    assert_eq!(s.xlm.balance(&cold_wallet), out);
+    assert_eq!(s.xlm.balance(&user), 0);
+    assert_eq!(s.sxlm.balance(&user), minted - 4_0000000);
+}
+
+#[test]
+fn test_redeem_from_uses_allowance() {
+    let s = setup();
+    let owner = funded_user(&s, 10_0000000);
+    let spender = Address::generate(&s.env);
+    let receiver = Address::generate(&s.env);
+    s.vault.deposit(&owner, &10_0000000);
+
+    assert_eq!(
+        s.vault.try_redeem_from(&spender, &owner, &receiver, &1_0000000),
+        Err(Ok(VaultError::InsufficientAllowance))
+    );
+
+    s.sxlm.approve(&owner, &spender, &3_0000000, &1000);
+    let out = s.vault.redeem_from(&spender, &owner, &receiver, &2_0000000);
+    assert_eq!(out, 2_0000000);
+    assert_eq!(s.xlm.balance(&receiver), 2_0000000);
+    assert_eq!(s.sxlm.balance(&owner), 8_0000000);
+    assert_eq!(s.sxlm.allowance(&owner, &spender), 1_0000000);
+
+    assert_eq!(
+        s.vault.try_redeem_from(&spender, &owner, &receiver, &2_0000000),
+        Err(Ok(VaultError::InsufficientAllowance))
+    );
+}
+
+#[test]
+fn test_mul_div_rounding() {
+    use crate::math::{mul_div, Rounding};
+
+    assert_eq!(mul_div(7, 3, 2, Rounding::Floor), Ok(10));
+    assert_eq!(mul_div(7, 3, 2, Rounding::Ceil), Ok(11));
+    assert_eq!(mul_div(6, 3, 2, Rounding::Ceil), Ok(9));
+    assert_eq!(mul_div(0, 3, 2, Rounding::Ceil), Ok(0));
+    assert_eq!(mul_div(1, 1, 0, Rounding::Floor), Err(VaultError::Overflow));
+    assert_eq!(mul_div(i128::MAX, 2, 1, Rounding::Floor), Err(VaultError::Overflow));
+
+    let mut rng = Rng(0x5eed);
+    for _ in 0..1000 {
+        let a = rng.range(0, i64::MAX as i128);
+        let b = rng.range(0, i64::MAX as i128);
+        let d = rng.range(1, i64::MAX as i128);
+        let floor = mul_div(a, b, d, Rounding::Floor).unwrap();
+        let ceil = mul_div(a, b, d, Rounding::Ceil).unwrap();
+        assert!(floor * d <= a * b && a * b <= ceil * d);
+        assert!(ceil - floor <= 1);
+    }
+}
+
+#[test]
+fn test_round_trip_never_extracts_value() {
+    let s = setup();
+    s.vault.set_vesting_period(&0);
+    let mut rng = Rng(0xdecafbad);
+    for _ in 0..25 {
+        // Random new holders and realized yield keep moving the rate off 1:1
+        let holder_deposit = rng.range(MIN_AMOUNT, 10_000 * PRECISION);
+        let holder = funded_user(&s, holder_deposit);
+        s.vault.deposit(&holder, &holder_deposit);
+        s.vault.add_yield(&s.admin, &rng.range(1, 2 * holder_deposit));
+
+        // deposit -> withdraw
+        let amount = rng.range(10 * MIN_AMOUNT, 10_000 * PRECISION);
+        let user = funded_user(&s, 2 * amount);
+        let shares = s.vault.deposit(&user, &amount);
+        assert!(s.vault.withdraw(&user, &shares) <= amount);
+
+        // mint -> withdraw
+        let shares = s.vault.convert_to_shares(&amount);
+        let cost = s.vault.mint(&user, &shares);
+        assert!(s.vault.withdraw(&user, &shares) <= cost);
+
+        // deposit -> withdraw_assets burns at least what was minted for it
+        let shares = s.vault.deposit(&user, &amount);
+        let value = s.vault.convert_to_assets(&shares);
+        if value >= MIN_AMOUNT {
+            assert!(s.vault.withdraw_assets(&user, &value) <= shares);
+        }
+    }
+}
+
+#[test]
+fn test_roles_gate_privileged_functions() {
+    let s = setup();
+    let guardian = Address::generate(&s.env);
+    let keeper = funded_user(&s, 10_0000000);
+
+    // Nobody but the owner holds a role until it is granted
+    assert!(s.vault.has_role(&Role::Owner, &s.admin));
+    assert!(s.vault.has_role(&Role::Guardian, &s.admin));
+    assert!(!s.vault.has_role(&Role::Guardian, &guardian));
+    assert_eq!(s.vault.try_pause(&guardian), Err(Ok(VaultError::Unauthorized)));
+
+    s.vault.grant_role(&Role::Guardian, &guardian);
+    s.vault.grant_role(&Role::Keeper, &keeper);
+    s.vault.pause(&guardian);
+    s.vault.unpause(&guardian);
+
+    // Roles do not overlap
+    assert_eq!(
+        s.vault.try_add_yield(&guardian, &1_0000000),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    assert_eq!(
+        s.vault.try_schedule(&keeper, &TimelockAction::SetTotalCap(1)),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    assert_eq!(
+        s.vault.try_schedule(&keeper, &TimelockAction::SetPerformanceFee(0)),
+        Err(Ok(VaultError::Unauthorized))
+    );
+
+    // The keeper pays in yield from its own balance, not the owner's
+    let user = funded_user(&s, 10_0000000);
+    s.vault.deposit(&user, &10_0000000);
+    s.vault.add_yield(&keeper, &1_0000000);
+    assert_eq!(s.xlm.balance(&keeper), 9_0000000);
+
+    s.vault.revoke_role(&Role::Guardian, &guardian);
+    assert!(!s.vault.has_role(&Role::Guardian, &guardian));
+    assert_eq!(s.vault.try_pause(&guardian), Err(Ok(VaultError::Unauthorized)));
+
+    assert_eq!(
+        s.vault.try_grant_role(&Role::Owner, &guardian),
+        Err(Ok(VaultError::InvalidRole))
+    );
+}
+
+#[test]
+fn test_role_change_events() {
+    use soroban_sdk::{testutils::Events, vec, IntoVal};
+
+    let s = setup();
+    let strategist = Address::generate(&s.env);
+    s.vault.grant_role(&Role::Strategist, &strategist);
+
+    let expected = (
+        s.vault.address.clone(),
+        (Symbol::new(&s.env, "role_granted"), Role::Strategist).into_val(&s.env),
+        strategist.into_val(&s.env),
+    );
+    assert_eq!(s.env.events().all(), vec![&s.env, expected]);
+}
+
+#[test]
+fn test_two_step_admin_transfer() {
+    let s = setup();
+    let new_admin = Address::generate(&s.env);
+
+    timelocked(&s, TimelockAction::ProposeAdmin(new_admin.clone(), None));
+    assert_eq!(s.vault.admin(), s.admin);
+    assert_eq!(s.vault.pending_admin().unwrap().address, new_admin);
+
+    s.vault.cancel_admin_transfer();
+    assert_eq!(s.vault.try_accept_admin(), Err(Ok(VaultError::NoPendingTransfer)));
+
+    let expiry = s.env.ledger().sequence() + 100;
+    timelocked(&s, TimelockAction::ProposeAdmin(new_admin.clone(), Some(expiry)));
+    s.env.ledger().with_mut(|l| l.sequence_number = expiry + 1);
+    assert_eq!(s.vault.try_accept_admin(), Err(Ok(VaultError::TransferExpired)));
+
+    timelocked(&s, TimelockAction::ProposeAdmin(new_admin.clone(), None));
+    s.vault.accept_admin();
+    assert_eq!(s.vault.admin(), new_admin);
+    assert!(s.vault.has_role(&Role::Owner, &new_admin));
+    assert!(!s.vault.has_role(&Role::Owner, &s.admin));
+}
+
+#[test]
+fn test_second_vault_mints_within_cap() {
+    let s = setup();
+    let second = VaultClient::new(&s.env, &s.env.register(Vault, ()));
+    second.initialize(&s.admin, &s.xlm.address, &s.sxlm.address);
+    s.sxlm.add_minter(&second.address, &10_0000000);
+
+    let user = funded_user(&s, 20_0000000);
+    let shares = second.deposit(&user, &8_0000000);
+    assert_eq!(s.sxlm.balance(&user), shares);
+    assert_eq!(s.sxlm.minter_headroom(&second.address), 10_0000000 - shares);
+    assert!(second.try_deposit(&user, &3_0000000).is_err());
+
+    // Redeeming through the second vault frees its allowance, whether the
+    // owner redeems or an approved spender redeems on their behalf
+    let spender = Address::generate(&s.env);
+    s.sxlm.approve(&user, &spender, &3_0000000, &1000);
+    second.redeem_from(&spender, &user, &user, &3_0000000);
+    assert_eq!(s.sxlm.minter_headroom(&second.address), 10_0000000 - shares + 3_0000000);
+    second.withdraw(&user, &(shares - 3_0000000));
+    assert_eq!(s.sxlm.minter_headroom(&second.address), 10_0000000);
+}
+
+#[test]
+fn test_vaults_hold_their_own_transfer_pause() {
+    let s = setup();
+    let second = VaultClient::new(&s.env, &s.env.register(Vault, ()));
+    second.initialize(&s.admin, &s.xlm.address, &s.sxlm.address);
+    s.sxlm.add_minter(&second.address, &10_0000000);
+
+    // Transfers stay paused until both vaults lift their pause
+    s.vault.set_paused(&s.admin, &Operation::Transfer, &true);
+    second.set_paused(&s.admin, &Operation::Transfer, &true);
+    s.vault.set_paused(&s.admin, &Operation::Transfer, &false);
+    assert!(second.pause_status().transfers);
+    assert!(s.vault.pause_status().transfers);
+
+    second.set_paused(&s.admin, &Operation::Transfer, &false);
+    assert!(!s.sxlm.transfers_paused());
+}
+
+#[test]
+fn test_vaults_price_against_their_own_shares() {
+    let s = setup();
+    let second = VaultClient::new(&s.env, &s.env.register(Vault, ()));
+    second.initialize(&s.admin, &s.xlm.address, &s.sxlm.address);
+    s.sxlm.add_minter(&second.address, &1000_0000000);
+
+    let alice = funded_user(&s, 100_0000000);
+    let bob = funded_user(&s, 200_0000000);
+    s.vault.deposit(&alice, &100_0000000);
+    second.deposit(&bob, &100_0000000);
+    assert_eq!(s.sxlm.total_supply(), 200_0000000);
+
+    // Yield in one vault moves only that vault's rate
+    s.vault.add_yield(&s.admin, &10_0000000);
+    vest(&s);
+    assert_close(s.vault.get_exchange_rate(), 1_1000000);
+    assert_eq!(second.get_exchange_rate(), PRECISION);
+    assert_eq!(second.preview_deposit(&100_0000000), 100_0000000);
+    assert_eq!(second.vault_state().total_supply, 100_0000000);
+
+    assert_close(s.vault.withdraw(&alice, &100_0000000), 110_0000000);
+    assert_eq!(second.withdraw(&bob, &100_0000000), 100_0000000);
+}
+
+#[test]
+fn test_sxlm_is_redeemed_only_where_it_was_minted() {
+    let s = setup();
+    let second = VaultClient::new(&s.env, &s.env.register(Vault, ()));
+    second.initialize(&s.admin, &s.xlm.address, &s.sxlm.address);
+    s.sxlm.add_minter(&second.address, &1000_0000000);
+
+    let alice = funded_user(&s, 100_0000000);
+    let bob = funded_user(&s, 100_0000000);
+    s.vault.deposit(&alice, &100_0000000);
+    s.vault.add_yield(&s.admin, &10_0000000);
+    vest(&s);
+    assert_close(s.vault.get_exchange_rate(), 1_1000000);
+
+    // sXLM minted at the cheaper vault cannot be redeemed at the dearer one,
+    // whether held directly or passed on to another holder
+    second.deposit(&bob, &100_0000000);
+    assert_eq!(second.get_exchange_rate(), PRECISION);
+    assert_eq!(s.vault.max_withdraw(&bob), 0);
+    assert_eq!(
+        s.vault.try_withdraw(&bob, &100_0000000),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+    assert_eq!(
+        s.vault.try_withdraw_assets(&bob, &10_0000000),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+    assert_eq!(
+        s.vault.try_request_withdraw(&bob, &100_0000000),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+    let carol = Address::generate(&s.env);
+    s.sxlm.transfer(&bob, &carol, &100_0000000);
+    assert_eq!(
+        s.vault.try_withdraw(&carol, &100_0000000),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+
+    // Back at the vault that issued it, it is worth what was paid in
+    assert_eq!(second.withdraw(&carol, &100_0000000), 100_0000000);
+    assert_close(s.vault.withdraw(&alice, &100_0000000), 110_0000000);
+    assert_eq!(s.sxlm.total_supply(), 0);
+}
+
+#[test]
+fn test_holder_burns_strand_no_xlm() {
+    let s = setup();
+    let second = VaultClient::new(&s.env, &s.env.register(Vault, ()));
+    second.initialize(&s.admin, &s.xlm.address, &s.sxlm.address);
+    s.sxlm.add_minter(&second.address, &1000_0000000);
+
+    let alice = funded_user(&s, 100_0000000);
+    let bob = funded_user(&s, 100_0000000);
+    let carol = funded_user(&s, 100_0000000);
+    s.vault.deposit(&alice, &100_0000000);
+    s.vault.deposit(&bob, &100_0000000);
+    second.deposit(&carol, &100_0000000);
+    let bob_value = s.vault.max_withdraw(&bob);
+    assert_eq!(bob_value, 100_0000000);
+
+    // Burning another vault's sXLM leaves this vault's holders untouched
+    s.sxlm.burn_self(&carol, &50_0000000);
+    assert_eq!(s.vault.max_withdraw(&bob), bob_value);
+    assert_eq!(s.sxlm.minter_outstanding(&s.vault.address), 200_0000000);
+    assert_eq!(s.sxlm.minter_outstanding(&second.address), 50_0000000);
+
+    // Burning this vault's sXLM leaves its XLM to the remaining holders
+    // instead of stranding it behind supply nobody holds
+    s.sxlm.burn_self(&alice, &50_0000000);
+    assert_eq!(s.sxlm.minter_outstanding(&s.vault.address), 150_0000000);
+    assert!(s.vault.max_withdraw(&bob) >= bob_value);
+
+    s.vault.withdraw(&alice, &50_0000000);
+    s.vault.withdraw(&bob, &100_0000000);
+    second.withdraw(&carol, &50_0000000);
+    assert_eq!(s.sxlm.total_supply(), 0);
+    // Only the virtual shares' slice of the burned sXLM's XLM stays behind
+    assert!(s.xlm.balance(&s.vault.address) <= 1_000);
+    assert!(s.xlm.balance(&second.address) <= 1_000);
+}
+
+#[test]
+fn test_vault_accepts_sxlm_minter() {
+    let s = setup();
+    let new_vault = VaultClient::new(&s.env, &s.env.register(Vault, ()));
+    new_vault.initialize(&s.admin, &s.xlm.address, &s.sxlm.address);
+
+    s.sxlm.propose_minter(&new_vault.address, &None);
+    assert_eq!(s.sxlm.minter(), s.vault.address);
+
+    new_vault.accept_sxlm_minter();
+    assert_eq!(s.sxlm.minter(), new_vault.address);
+}
+
+#[test]
+fn test_timelock_delays_execution() {
+    let s = setup();
+    let id = s
+        .vault
+        .schedule(&s.admin, &TimelockAction::SetTotalCap(50_0000000));
+    let op = s.vault.timelock_op(&id);
+    assert_eq!(op.eta, s.env.ledger().timestamp() + DEFAULT_TIMELOCK_DELAY);
+    assert_eq!(s.vault.timelock_ops(), soroban_sdk::vec![&s.env, id]);
+
+    assert_eq!(s.vault.try_execute(&id), Err(Ok(VaultError::TimelockNotReady)));
+    s.env.ledger().with_mut(|l| l.timestamp = op.eta);
+
+    // Execution is permissionless once the delay has passed
+    s.vault.execute(&id);
+    assert_eq!(s.vault.total_cap(), 50_0000000);
+    assert_eq!(s.vault.try_execute(&id), Err(Ok(VaultError::OperationNotFound)));
+    assert_eq!(s.vault.timelock_ops().len(), 0);
+}
+
+#[test]
+fn test_guardian_cancels_timelocked_op() {
+    let s = setup();
+    let guardian = Address::generate(&s.env);
+    s.vault.grant_role(&Role::Guardian, &guardian);
+
+    let cap = s.vault.total_cap();
+    let id = s
+        .vault
+        .schedule(&s.admin, &TimelockAction::SetTotalCap(1_0000000));
+    assert_eq!(
+        s.vault.try_cancel(&Address::generate(&s.env), &id),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    s.vault.cancel(&guardian, &id);
+
+    s.env.ledger().with_mut(|l| l.timestamp += DEFAULT_TIMELOCK_DELAY);
+    assert_eq!(s.vault.try_execute(&id), Err(Ok(VaultError::OperationNotFound)));
+    assert_eq!(s.vault.total_cap(), cap);
+}
+
+#[test]
+fn test_timelock_delay_is_itself_timelocked() {
+    let s = setup();
+    assert_eq!(
+        s.vault
+            .try_schedule(&s.admin, &TimelockAction::SetTimelockDelay(MAX_TIMELOCK_DELAY + 1)),
+        Err(Ok(VaultError::InvalidDelay))
+    );
+
+    timelocked(&s, TimelockAction::SetTimelockDelay(60));
+    assert_eq!(s.vault.timelock_delay(), 60);
+
+    let id = s
+        .vault
+        .schedule(&s.admin, &TimelockAction::SetMaxDeposit(5_0000000));
+    s.env.ledger().with_mut(|l| l.timestamp += 60);
+    s.vault.execute(&id);
+    assert_eq!(s.vault.max_deposit_per_tx(), 5_0000000);
+}
+
+#[test]
+fn test_migrate_legacy_storage() {
+    let s = setup();
+    let user = funded_user(&s, 10_0000000);
+    let holder = funded_user(&s, 10_0000000);
+    s.vault.deposit(&holder, &10_0000000);
+    assert_eq!(s.vault.schema_version(), 2);
+    assert_eq!(s.vault.try_migrate(&2), Err(Ok(VaultError::InvalidSchemaVersion)));
+
+    // Simulate a paused deployment from before schema versioning
+    s.env.as_contract(&s.vault.address, || {
+        s.env.storage().instance().remove(&DataKey::SchemaVersion);
+        s.env.storage().instance().remove(&DataKey::LastFeeAccrual);
+        s.env.storage().instance().set(&DataKey::Paused, &true);
+    });
+    assert_eq!(s.vault.schema_version(), 0);
+    assert_eq!(
+        s.vault.try_deposit(&user, &10_0000000),
+        Err(Ok(VaultError::SchemaOutdated))
+    );
+    assert_eq!(
+        s.vault.try_add_yield(&s.admin, &1_0000000),
+        Err(Ok(VaultError::SchemaOutdated))
+    );
+    // Transfers would write positions through the hook, so they wait too
+    assert!(s.sxlm.try_transfer(&holder, &user, &1_0000000).is_err());
+
+    // The guardian can still pause during a migration
+    s.vault.set_paused(&s.admin, &Operation::Yield, &true);
+    assert_eq!(s.vault.try_unpause(&s.admin), Err(Ok(VaultError::SchemaOutdated)));
+
+    assert_eq!(s.vault.try_migrate(&1), Err(Ok(VaultError::InvalidSchemaVersion)));
+    assert_eq!(s.vault.try_migrate(&3), Err(Ok(VaultError::InvalidSchemaVersion)));
+    s.vault.migrate(&2);
+    assert_eq!(s.vault.schema_version(), 2);
+    assert_eq!(s.vault.try_migrate(&2), Err(Ok(VaultError::InvalidSchemaVersion)));
+
+    // The legacy global pause carried over to every vault operation
+    let status = s.vault.pause_status();
+    assert!(status.deposits && status.withdrawals && status.yield_additions && status.strategies);
+    assert_eq!(
+        s.vault.try_deposit(&user, &10_0000000),
+        Err(Ok(VaultError::Paused))
+    );
+
+    s.vault.unpause(&s.admin);
+    s.vault.deposit(&user, &10_0000000);
+    s.sxlm.transfer(&holder, &user, &1_0000000);
+}
+
+#[test]
+fn test_pause_flags_are_independent() {
+    let s = setup();
+    let user = funded_user(&s, 20_0000000);
+    s.vault.deposit(&user, &10_0000000);
+
+    // Stop new deposits while users can still exit
+    s.vault.set_paused(&s.admin, &Operation::Deposit, &true);
+    assert_eq!(s.vault.try_deposit(&user, &1_0000000), Err(Ok(VaultError::Paused)));
+    assert_eq!(s.vault.try_mint(&user, &1_0000000), Err(Ok(VaultError::Paused)));
+    assert_eq!(s.vault.max_deposit(&user), 0);
+    s.vault.withdraw(&user, &1_0000000);
+    s.vault.add_yield(&s.admin, &1_0000000);
+
+    s.vault.set_paused(&s.admin, &Operation::Yield, &true);
+    assert_eq!(s.vault.try_add_yield(&s.admin, &1_0000000), Err(Ok(VaultError::Paused)));
+
+    add_mock_strategy(&s, 5_000);
+    s.vault.set_paused(&s.admin, &Operation::Strategy, &true);
+    assert_eq!(s.vault.try_rebalance(&s.admin), Err(Ok(VaultError::Paused)));
+
+    s.vault.set_paused(&s.admin, &Operation::Transfer, &true);
+    assert!(s.sxlm.transfers_paused());
+
+    let status = s.vault.pause_status();
+    assert_eq!(
+        status,
+        PauseStatus {
+            deposits: true,
+            withdrawals: false,
+            yield_additions: true,
+            strategies: true,
+            transfers: true,
+            withdraw_only: false,
+            shutdown: false,
+        }
+    );
+
+    // Unpausing clears the vault operations but leaves transfers to the guardian
+    s.vault.unpause(&s.admin);
+    s.vault.deposit(&user, &1_0000000);
+    assert!(s.vault.pause_status().transfers);
+    s.vault.set_paused(&s.admin, &Operation::Transfer, &false);
+    assert!(!s.sxlm.transfers_paused());
+}
+
+#[test]
+fn test_pause_flag_events() {
+    use soroban_sdk::{testutils::Events, vec, IntoVal};
+
+    let s = setup();
+    s.vault.set_paused(&s.admin, &Operation::Withdraw, &true);
+
+    let expected = (
+        s.vault.address.clone(),
+        (Symbol::new(&s.env, "pause_set"), Operation::Withdraw).into_val(&s.env),
+        true.into_val(&s.env),
+    );
+    assert_eq!(s.env.events().all(), vec![&s.env, expected]);
+}
+
+#[test]
+fn test_withdraw_only_mode_redeems_pro_rata() {
+    let s = setup();
+    let alice = funded_user(&s, 60_0000000);
+    let bob = funded_user(&s, 40_0000000);
+    s.vault.deposit(&alice, &60_0000000);
+    s.vault.deposit(&bob, &40_0000000);
+
+    let strategy = add_mock_strategy(&s, 8_000);
+    s.vault.rebalance(&s.admin);
+    assert_eq!(s.xlm.balance(&s.vault.address), 20_0000000);
+
+    // The strategy loses 10 XLM
+    s.env.as_contract(&strategy, || {
+        token::Client::new(&s.env, &s.xlm.address).transfer(
+            &strategy,
+            &Address::generate(&s.env),
+            &10_0000000,
+        );
+    });
+
+    s.vault.set_withdraw_only(&s.admin, &true);
+    assert!(s.vault.pause_status().withdraw_only);
+    assert_eq!(s.vault.try_deposit(&alice, &1_0000000), Err(Ok(VaultError::Paused)));
+    assert_eq!(s.vault.try_rebalance(&s.admin), Err(Ok(VaultError::Paused)));
+    assert_eq!(
+        s.vault.try_withdraw_assets(&alice, &1_0000000),
+        Err(Ok(VaultError::Paused))
+    );
+    assert_eq!(
+        s.vault.try_request_withdraw(&alice, &1_0000000),
+        Err(Ok(VaultError::Paused))
+    );
+
+    // Alice exits first and takes 60% of idle and 60% of the strategy
+    // rather than draining the 20 XLM idle balance
+    let alice_out = s.vault.withdraw(&alice, &s.sxlm.balance(&alice));
+    assert_close(alice_out, 54_0000000);
+    assert!((s.xlm.balance(&s.vault.address) - 8_0000000).abs() < 1_000);
+
+    // Bob gets the same per-share value
+    let bob_out = s.vault.withdraw(&bob, &s.sxlm.balance(&bob));
+    assert_close(bob_out, 36_0000000);
+}
+
+#[test]
+fn test_emergency_shutdown_freezes_rate_and_records_losses() {
+    use soroban_sdk::{testutils::Events, IntoVal};
+
+    let s = setup();
+    let alice = funded_user(&s, 60_0000000);
+    let bob = funded_user(&s, 40_0000000);
+    s.vault.deposit(&alice, &60_0000000);
+    s.vault.deposit(&bob, &40_0000000);
+
+    let healthy = add_mock_strategy(&s, 4_000);
+    let compromised = add_mock_strategy(&s, 4_000);
+    s.vault.rebalance(&s.admin);
+    MockStrategyClient::new(&s.env, &compromised).freeze(&10_0000000);
+
+    assert_eq!(
+        s.vault.try_emergency_redeem(&alice, &1_0000000),
+        Err(Ok(VaultError::NotShutdown))
+    );
+
+    let state = s.vault.emergency_shutdown(&s.admin);
+    let loss_event = (
+        s.vault.address.clone(),
+        (Symbol::new(&s.env, "strategy_loss"), compromised.clone()).into_val(&s.env),
+        (40_0000000i128, 30_0000000i128, 10_0000000i128).into_val(&s.env),
+    );
+    assert!(s.env.events().all().contains(&loss_event));
+    assert_eq!(state.total_loss, 10_0000000);
+    assert_eq!(s.vault.strategy_loss(&compromised), 10_0000000);
+    assert_eq!(s.vault.strategy_loss(&healthy), 0);
+    assert_eq!(s.vault.strategies().len(), 0);
+    assert_eq!(state.assets, 90_0000000);
+    assert_eq!(s.vault.shutdown_state(), Some(state.clone()));
+
+    // Terminal: nothing reopens the vault
+    assert!(s.vault.is_paused());
+    assert!(s.vault.pause_status().shutdown);
+    s.vault.unpause(&s.admin);
+    assert_eq!(s.vault.try_deposit(&alice, &1_0000000), Err(Ok(VaultError::VaultShutdown)));
+    assert_eq!(
+        s.vault.try_withdraw(&alice, &1_0000000),
+        Err(Ok(VaultError::VaultShutdown))
+    );
+    assert_eq!(
+        s.vault.try_add_strategy(&s.admin, &healthy, &1_000),
+        Err(Ok(VaultError::VaultShutdown))
+    );
+    assert_eq!(
+        s.vault.try_emergency_shutdown(&s.admin),
+        Err(Ok(VaultError::VaultShutdown))
+    );
+
+    // Each holder gets the same frozen rate regardless of exit order
+    let bob_shares = s.sxlm.balance(&bob);
+    let bob_quote = s.vault.preview_emergency_redeem(&bob_shares);
+    let alice_out = s.vault.emergency_redeem(&alice, &s.sxlm.balance(&alice));
+    assert_close(alice_out, 54_0000000);
+    assert_eq!(s.vault.preview_emergency_redeem(&bob_shares), bob_quote);
+    assert_eq!(s.vault.emergency_redeem(&bob, &bob_shares), bob_quote);
+    assert_close(bob_quote, 36_0000000);
+    assert!(s.xlm.balance(&s.vault.address) < 10);
+}
+
+#[test]
+fn test_no_fees_accrue_after_shutdown() {
+    let s = setup();
+    let alice = funded_user(&s, 60_0000000);
+    let bob = funded_user(&s, 40_0000000);
+    let treasury = Address::generate(&s.env);
+    s.vault.deposit(&alice, &60_0000000);
+    s.vault.deposit(&bob, &40_0000000);
+    s.vault.set_treasury(&s.admin, &treasury);
+    timelocked(&s, TimelockAction::SetManagementFee(200));
+
+    let state = s.vault.emergency_shutdown(&s.admin);
+    let supply = s.sxlm.total_supply();
+    assert_eq!(state.supply, supply);
+
+    // Fee changes after shutdown settle nothing against the frozen snapshot
+    s.env.ledger().with_mut(|l| l.timestamp += SECONDS_PER_YEAR);
+    let new_treasury = Address::generate(&s.env);
+    s.vault.set_treasury(&s.admin, &new_treasury);
+    timelocked(&s, TimelockAction::SetManagementFee(100));
+    assert_eq!(s.sxlm.total_supply(), supply);
+    assert_eq!(s.sxlm.balance(&new_treasury), 0);
+
+    // Every holder, the fee treasury included, can still exit in full
+    for holder in [&alice, &bob, &treasury] {
+        let shares = s.sxlm.balance(holder);
+        if shares > 0 {
+            s.vault.emergency_redeem(holder, &shares);
+        }
+    }
+    assert_eq!(s.sxlm.total_supply(), 0);
+    assert!(s.xlm.balance(&s.vault.address) < 10);
+}
+
+#[test]
+fn test_emergency_shutdown_requires_guardian() {
+    let s = setup();
+    let guardian = Address::generate(&s.env);
+    assert_eq!(
+        s.vault.try_emergency_shutdown(&guardian),
+        Err(Ok(VaultError::Unauthorized))
+    );
+
+    s.vault.grant_role(&Role::Guardian, &guardian);
+    s.vault.emergency_shutdown(&guardian);
+    assert!(s.vault.shutdown_state().is_some());
+}
+
+#[test]
+fn test_per_address_caps_track_position_value() {
+    use soroban_sdk::vec;
+
+    let s = setup();
+    let alice = funded_user(&s, 200_0000000);
+    let whale = funded_user(&s, 200_0000000);
+
+    s.vault.set_default_user_cap(&s.admin, &100_0000000);
+    s.vault
+        .set_user_caps(&s.admin, &vec![&s.env, whale.clone()], &Some(150_0000000));
+    assert_eq!(s.vault.user_cap(&whale), 150_0000000);
+
+    // Looping small deposits cannot get past the cap
+    s.vault.deposit(&alice, &60_0000000);
+    s.vault.deposit(&alice, &40_0000000);
+    assert_eq!(
+        s.vault.try_deposit(&alice, &1_0000000),
+        Err(Ok(VaultError::UserCapExceeded))
+    );
+    assert_eq!(s.vault.deposit_limit(&alice), 0);
+    assert_eq!(s.vault.max_deposit(&alice), 0);
+
+    s.vault.deposit(&whale, &150_0000000);
+    assert_eq!(s.vault.deposit_limit(&whale), 0);
+
+    // Clearing the override falls back to the default cap
+    s.vault.set_user_caps(&s.admin, &vec![&s.env, whale.clone()], &None);
+    assert_eq!(s.vault.user_cap(&whale), 100_0000000);
+    assert_eq!(
+        s.vault.try_deposit(&whale, &1_0000000),
+        Err(Ok(VaultError::UserCapExceeded))
+    );
+
+    // Redeeming frees headroom
+    s.vault.withdraw(&alice, &(s.sxlm.balance(&alice) / 2));
+    assert!((s.vault.deposit_limit(&alice) - 50_0000000).abs() <= 1);
+
+    // The cap applies to the receiver of deposit_for
+    assert_eq!(
+        s.vault.try_deposit_for(&whale, &alice, &60_0000000),
+        Err(Ok(VaultError::UserCapExceeded))
+    );
+}
+
+#[test]
+fn test_allowlist_mode_gates_deposits() {
+    use soroban_sdk::vec;
+
+    let s = setup();
+    let alice = funded_user(&s, 100_0000000);
+    let bob = funded_user(&s, 100_0000000);
+    let outsider = Address::generate(&s.env);
+
+    assert_eq!(
+        s.vault.try_set_allowlist_enabled(&outsider, &true),
+        Err(Ok(VaultError::Unauthorized))
+    );
+
+    s.vault.set_allowlist_enabled(&s.admin, &true);
+    assert!(s.vault.allowlist_enabled());
+    s.vault
+        .add_to_allowlist(&s.admin, &vec![&s.env, alice.clone(), bob.clone()]);
+    assert!(s.vault.is_allowlisted(&alice));
+    assert_eq!(s.vault.deposit_limit(&outsider), 0);
+
+    s.vault.deposit(&alice, &10_0000000);
+    s.vault.remove_from_allowlist(&s.admin, &vec![&s.env, bob.clone()]);
+    assert_eq!(s.vault.try_deposit(&bob, &10_0000000), Err(Ok(VaultError::NotAllowlisted)));
+    assert_eq!(
+        s.vault.try_deposit_for(&alice, &bob, &10_0000000),
+        Err(Ok(VaultError::NotAllowlisted))
+    );
+
+    // Removal never traps an existing position
+    s.vault.remove_from_allowlist(&s.admin, &vec![&s.env, alice.clone()]);
+    s.vault.withdraw(&alice, &s.sxlm.balance(&alice));
+
+    s.vault.set_allowlist_enabled(&s.admin, &false);
+    s.vault.deposit(&bob, &10_0000000);
+}
+
+#[test]
+fn test_outflow_limit_queues_excess_withdrawals() {
+    let s = setup();
+    let whale = funded_user(&s, 100_0000000);
+    s.vault.deposit(&whale, &100_0000000);
+    assert_eq!(s.vault.withdraw_capacity(), 100_0000000);
+
+    assert_eq!(
+        s.vault.try_set_outflow_limit(&s.admin, &0, &Some(30_0000000), &None),
+        Err(Ok(VaultError::InvalidOutflowLimit))
+    );
+    s.vault.set_outflow_limit(&s.admin, &100, &Some(30_0000000), &None);
+    assert_eq!(s.vault.withdraw_capacity(), 30_0000000);
+
+    // Within the limit: paid instantly
+    s.vault.withdraw(&whale, &20_0000000);
+    assert_eq!(s.xlm.balance(&whale), 20_0000000);
+    assert_eq!(s.vault.withdraw_capacity(), 10_0000000);
+    assert_eq!(s.vault.max_withdraw(&whale), 10_0000000);
+
+    // Over the limit: queued rather than failing
+    let tail = s.vault.queue_tail();
+    assert_eq!(s.vault.withdraw(&whale, &20_0000000), 20_0000000);
+    assert_eq!(s.xlm.balance(&whale), 20_0000000);
+    let ticket = s.vault.get_withdraw_ticket(&tail);
+    assert_eq!(ticket.owner, whale);
+    assert_eq!(ticket.xlm_amount, 20_0000000);
+    s.vault.process_queue(&10);
+    s.vault.claim_withdraw(&whale, &tail);
+    assert_eq!(s.xlm.balance(&whale), 40_0000000);
+
+    // Used capacity decays over the window
+    s.env.ledger().with_mut(|l| l.sequence_number += 50);
+    assert_eq!(s.vault.withdraw_capacity(), 20_0000000);
+    s.env.ledger().with_mut(|l| l.sequence_number += 50);
+    assert_eq!(s.vault.withdraw_capacity(), 30_0000000);
+
+    // A bps limit tracks total assets
+    s.vault.set_outflow_limit(&s.admin, &100, &None, &Some(1_000));
+    assert_eq!(s.vault.withdraw_capacity(), 6_0000000);
+    assert_eq!(
+        s.vault.withdraw_assets(&whale, &10_0000000),
+        s.vault.get_withdraw_ticket(&(tail + 1)).sxlm_amount
+    );
+
+    s.vault.set_outflow_limit(&s.admin, &0, &None, &None);
+    assert_eq!(s.vault.outflow_limit(), None);
+    assert_eq!(s.vault.withdraw_capacity(), 50_0000000);
+}
+
+#[test]
+fn test_legacy_depositor_position_is_seeded() {
+    let s = setup();
+    let alice = funded_user(&s, 100_0000000);
+    let bob = Address::generate(&s.env);
+    s.vault.deposit(&alice, &100_0000000);
+
+    // A depositor from before cost tracking only has the deposit counter
+    s.env.as_contract(&s.vault.address, || {
+        let storage = s.env.storage().persistent();
+        storage.remove(&DataKey::Position(alice.clone()));
+        storage.set(&DataKey::UserDeposit(alice.clone()), &100_0000000i128);
+        storage.extend_ttl(&DataKey::UserDeposit(alice.clone()), 100_000, 100_000);
+    });
+    s.vault.add_yield(&s.admin, &10_0000000);
+    vest(&s);
+
+    let position = s.vault.position(&alice);
+    assert_eq!(position.cost_basis, 100_0000000);
+    assert_close(position.unrealized_pnl, 10_0000000);
+
+    // Transfers wait for the position to be synced, which retires the counter
+    let quarter = s.sxlm.balance(&alice) / 4;
+    assert!(s.sxlm.try_transfer(&alice, &bob, &quarter).is_err());
+    s.vault.sync_position(&alice);
+    s.sxlm.transfer(&alice, &bob, &quarter);
+    assert_close(s.vault.position(&bob).cost_basis, 25_0000000);
+    assert_close(s.vault.position(&alice).cost_basis, 75_0000000);
+    s.env.as_contract(&s.vault.address, || {
+        assert!(!s.env.storage().persistent().has(&DataKey::UserDeposit(alice.clone())));
+    });
+
+    let out = s.vault.withdraw(&alice, &quarter);
+    assert_close(s.vault.position(&alice).realized_pnl, out - 25_0000000);
+    assert_close(s.vault.position(&alice).realized_pnl, 2_5000000);
+    assert_close(s.vault.get_user_deposit(&alice), 50_0000000);
+
+    // Vault operations sync a legacy holder on their own
+    let carol = funded_user(&s, 10_0000000);
+    s.vault.deposit(&carol, &10_0000000);
+    s.env.as_contract(&s.vault.address, || {
+        let storage = s.env.storage().persistent();
+        storage.remove(&DataKey::Position(carol.clone()));
+        storage.set(&DataKey::UserDeposit(carol.clone()), &10_0000000i128);
+    });
+    let carol_shares = s.sxlm.balance(&carol);
+    let out = s.vault.withdraw(&carol, &(carol_shares / 2));
+    assert_close(s.vault.position(&carol).realized_pnl, out - 5_0000000);
+    assert_close(s.vault.get_user_deposit(&carol), 5_0000000);
+}
+
+#[test]
+fn test_position_tracks_cost_basis_and_pnl() {
+    let s = setup();
+    let alice = funded_user(&s, 200_0000000);
+    let bob = Address::generate(&s.env);
+
+    // 100 XLM at 1.0, then 55 XLM at 1.1: average cost ~1.0333
+    s.vault.deposit(&alice, &100_0000000);
+    s.vault.add_yield(&s.admin, &10_0000000);
+    vest(&s);
+    s.vault.deposit(&alice, &55_0000000);
+    let position = s.vault.position(&alice);
+    assert_eq!(position.cost_basis, 155_0000000);
+    assert_close(position.shares, 150_0000000);
+    assert_close(position.avg_cost, 1_0333333);
+    assert_close(position.value, 165_0000000);
+    assert_close(position.unrealized_pnl, 10_0000000);
+
+    // A transfer moves a third of the cost to bob and realizes nothing
+    let third = position.shares / 3;
+    s.sxlm.transfer(&alice, &bob, &third);
+    let bob_position = s.vault.position(&bob);
+    assert_close(bob_position.cost_basis, 51_6666666);
+    assert_eq!(bob_position.realized_pnl, 0);
+    assert_close(s.vault.position(&alice).cost_basis, 103_3333333);
+
+    // Withdrawing realizes proceeds minus average cost
+    let out = s.vault.withdraw(&bob, &third);
+    let bob_position = s.vault.position(&bob);
+    assert_eq!(bob_position.shares, 0);
+    assert_eq!(bob_position.cost_basis, 0);
+    assert_close(bob_position.realized_pnl, out - 51_6666666);
+    assert_close(bob_position.realized_pnl, 3_3333333);
+
+    // Withdrawing everything, including yield, realizes the rest
+    s.vault.withdraw(&alice, &s.sxlm.balance(&alice));
+    let alice_position = s.vault.position(&alice);
+    assert_eq!(alice_position.cost_basis, 0);
+    assert_close(alice_position.realized_pnl, 6_6666666);
+    assert_eq!(s.vault.get_user_deposit(&alice), 0);
+}
+
+#[test]
+fn test_transfer_hook_only_accepts_the_token() {
+    let s = setup();
+    let alice = Address::generate(&s.env);
+    let bob = Address::generate(&s.env);
+    s.env.mock_auths(&[]);
+    assert!(s.vault.try_on_transfer(&alice, &bob, &1).is_err());
+}
+
+#[test]
+fn test_state_views_match_individual_views() {
+    let s = setup();
+    let user = funded_user(&s, 100_0000000);
+    s.vault.deposit(&user, &100_0000000);
+    let strategy = add_mock_strategy(&s, 5_000);
+    s.vault.rebalance(&s.admin);
+    s.vault.add_yield(&s.admin, &10_0000000);
+
+    let state = s.vault.vault_state();
+    assert_eq!(state.total_assets, s.vault.get_total_assets());
+    assert_eq!(state.idle_assets, s.vault.get_idle_assets());
+    assert_eq!(state.total_supply, s.sxlm.total_supply());
+    assert_eq!(state.exchange_rate, s.vault.get_exchange_rate());
+    assert_eq!(state.locked_yield, s.vault.locked_yield());
+    assert_eq!(state.pause, s.vault.pause_status());
+    assert_eq!(state.max_deposit_per_tx, s.vault.max_deposit_per_tx());
+    assert_eq!(state.total_cap, s.vault.total_cap());
+    assert_eq!(state.fees, s.vault.preview_fees());
+    assert_eq!(state.pending_withdrawals, s.vault.get_pending_withdrawals());
+    assert_eq!(state.withdraw_capacity, s.vault.withdraw_capacity());
+    assert_eq!(state.schema_version, s.vault.schema_version());
+    assert_eq!(state.strategies.len(), 1);
+    let reported = state.strategies.get(0).unwrap();
+    assert_eq!(reported.strategy, strategy);
+    assert_eq!(reported.weight_bps, 5_000);
+    assert_eq!(reported.deployed, 50_0000000);
+
+    let user_state = s.vault.user_state(&user);
+    assert_eq!(user_state.position, s.vault.position(&user));
+    assert_eq!(user_state.xlm_balance, 0);
+    assert_eq!(user_state.max_deposit, s.vault.max_deposit(&user));
+    assert_eq!(user_state.max_withdraw, s.vault.max_withdraw(&user));
+    assert_eq!(user_state.deposit_limit, s.vault.deposit_limit(&user));
+    assert!(!user_state.allowlisted);
+}
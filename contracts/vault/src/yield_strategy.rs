@@ -1,86 +1,22 @@
-// Example yield strategy integration
-// This would connect to real DeFi protocols on Soroban
+// Yield strategy interface
+// Any contract registered with the vault via `add_strategy` must expose
+// these entrypoints. `contracts/yield-strategy` (Soroswap LP) implements it.
 
-use soroban_sdk::{contract, contractimpl, Address, Env};
+use soroban_sdk::{contractclient, Address, Env};
 
+#[contractclient(name = "StrategyClient")]
 pub trait YieldStrategy {
-    // Deposit assets into yield source
-    fn deposit(env: &Env, amount: i128) -> i128;
+    /// Pull `amount` XLM from `from` (the vault) into the yield source.
+    /// Returns the XLM amount accepted.
+    fn deposit(env: Env, from: Address, amount: i128) -> i128;
 
-    // Withdraw assets from yield source
-    fn withdraw(env: &Env, amount: i128) -> i128;
+    /// Send up to `amount` XLM back to `to` (the vault).
+    /// Returns the XLM amount actually sent.
+    fn withdraw(env: Env, to: Address, amount: i128) -> i128;
 
-    // Get current value (principal + yield)
-    fn get_balance(env: &Env) -> i128;
+    /// Current value of the position in XLM (principal + yield)
+    fn balance(env: Env) -> i128;
 
-    // Harvest and compound yield
-    fn harvest(env: &Env) -> i128;
-}
-
-// Example: Soroswap AMM Strategy
-pub struct SoroswapStrategy {
-    pub pool_address: Address,
-    pub router_address: Address,
-}
-
-impl YieldStrategy for SoroswapStrategy {
-    fn deposit(env: &Env, amount: i128) -> i128 {
-        // 1. Swap half XLM to paired token (e.g., USDC)
-        // 2. Add liquidity to XLM/USDC pool
-        // 3. Receive LP tokens
-        // 4. Store LP token balance
-        amount
-    }
-
-    fn withdraw(env: &Env, amount: i128) -> i128 {
-        // 1. Calculate LP tokens to burn
-        // 2. Remove liquidity
-        // 3. Swap paired token back to XLM
-        // 4. Return XLM
-        amount
-    }
-
-    fn get_balance(env: &Env) -> i128 {
-        // 1. Get LP token balance
-        // 2. Calculate underlying XLM value
-        // 3. Include accumulated trading fees
-        0
-    }
-
-    fn harvest(env: &Env) -> i128 {
-        // 1. Claim any reward tokens
-        // 2. Swap rewards to XLM
-        // 3. Return yield amount
-        0
-    }
-}
-
-// Example: Lending Protocol Strategy
-pub struct LendingStrategy {
-    pub lending_pool: Address,
-}
-
-impl YieldStrategy for LendingStrategy {
-    fn deposit(env: &Env, amount: i128) -> i128 {
-        // 1. Deposit XLM to lending pool
-        // 2. Receive aXLM (interest-bearing token)
-        amount
-    }
-
-    fn withdraw(env: &Env, amount: i128) -> i128 {
-        // 1. Burn aXLM
-        // 2. Receive XLM + accrued interest
-        amount
-    }
-
-    fn get_balance(env: &Env) -> i128 {
-        // aXLM balance * exchange rate = XLM value
-        0
-    }
-
-    fn harvest(env: &Env) -> i128 {
-        // Interest accrues automatically in aXLM
-        // No manual harvest needed
-        0
-    }
+    /// Claim and compound rewards. Returns the yield realized in XLM.
+    fn harvest(env: Env) -> i128;
 }
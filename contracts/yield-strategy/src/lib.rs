@@ -1,5 +1,8 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, Vec, Symbol, symbol_short};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env,
+    IntoVal, Vec,
+};
 
 #[contracttype]
 pub enum DataKey {
@@ -13,13 +16,68 @@ pub enum DataKey {
     TotalDeposited,
 }
 
-// Soroswap Router interface
-mod router {
-    use soroban_sdk::{Address, Env, Vec};
+/// Stable error codes returned by the strategy entrypoints.
+/// Codes are part of the public interface: never renumber, only append.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum StrategyError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    NotVault = 3,
+    InvalidAmount = 4,
+    Overflow = 5,
+    ZeroValue = 6, // LP tokens are held but the position is valued at zero
+    InvalidRouterResponse = 7,
+}
+
+// The Soroswap router is called by function name through `invoke_contract`
+// (`swap_ext`, `add_liq`, `rem_liq`), so no router wasm is imported.
+
+/// `a * b / denominator` rounded down, for non-negative inputs
+fn mul_div(a: i128, b: i128, denominator: i128) -> Result<i128, StrategyError> {
+    if denominator == 0 {
+        return Err(StrategyError::Overflow);
+    }
+    a.checked_mul(b)
+        .map(|product| product / denominator)
+        .ok_or(StrategyError::Overflow)
+}
+
+/// Output amount of a two-hop `swap_ext`, the last entry of its amounts
+fn swap_output(amounts: &Vec<i128>) -> Result<i128, StrategyError> {
+    amounts.get(1).ok_or(StrategyError::InvalidRouterResponse)
+}
 
-    soroban_sdk::contractimport!(
-        file = "../soroswap_router.wasm"
-    );
+fn get_address(env: &Env, key: &DataKey) -> Result<Address, StrategyError> {
+    env.storage()
+        .instance()
+        .get(key)
+        .ok_or(StrategyError::NotInitialized)
+}
+
+fn get_amount(env: &Env, key: &DataKey) -> i128 {
+    env.storage().instance().get(key).unwrap_or(0)
+}
+
+/// Authorize the vault and check it is the address named in the call
+fn require_vault(env: &Env, caller: &Address) -> Result<(), StrategyError> {
+    let vault = get_address(env, &DataKey::Vault)?;
+    if *caller != vault {
+        return Err(StrategyError::NotVault);
+    }
+    vault.require_auth();
+    Ok(())
+}
+
+/// Value of the LP position in XLM. Reported at principal: the vault prices
+/// shares against this, so it must not count fees or price moves that
+/// `withdraw` cannot yet be shown to recover.
+fn position_value(env: &Env) -> Result<i128, StrategyError> {
+    if get_amount(env, &DataKey::LpBalance) == 0 {
+        return Ok(0);
+    }
+    Ok(get_amount(env, &DataKey::TotalDeposited))
 }
 
 #[contract]
@@ -36,9 +94,9 @@ impl YieldStrategy {
         factory: Address,
         xlm_token: Address,
         usdc_token: Address,
-    ) {
+    ) -> Result<(), StrategyError> {
         if env.storage().instance().has(&DataKey::Admin) {
-            panic!("Already initialized");
+            return Err(StrategyError::AlreadyInitialized);
         }
 
         env.storage().instance().set(&DataKey::Admin, &admin);
@@ -49,24 +107,27 @@ impl YieldStrategy {
         env.storage().instance().set(&DataKey::UsdcToken, &usdc_token);
         env.storage().instance().set(&DataKey::LpBalance, &0i128);
         env.storage().instance().set(&DataKey::TotalDeposited, &0i128);
+        Ok(())
     }
 
+    // Implements the vault's `YieldStrategy` interface
+    // (deposit / withdraw / balance / harvest), see contracts/vault/src/yield_strategy.rs
+
     /// Deposit XLM into Soroswap liquidity pool
+    /// Returns the XLM amount accepted
     /// 1. Receive XLM from vault
     /// 2. Swap half to USDC
     /// 3. Add liquidity to XLM/USDC pool
     /// 4. Hold LP tokens
-    pub fn deposit(env: Env, from: Address, amount: i128) -> i128 {
-        from.require_auth();
-
-        let vault: Address = env.storage().instance().get(&DataKey::Vault).unwrap();
-        if from != vault {
-            panic!("Only vault can deposit");
+    pub fn deposit(env: Env, from: Address, amount: i128) -> Result<i128, StrategyError> {
+        require_vault(&env, &from)?;
+        if amount <= 0 {
+            return Err(StrategyError::InvalidAmount);
         }
 
-        let xlm_token: Address = env.storage().instance().get(&DataKey::XlmToken).unwrap();
-        let usdc_token: Address = env.storage().instance().get(&DataKey::UsdcToken).unwrap();
-        let router: Address = env.storage().instance().get(&DataKey::Router).unwrap();
+        let xlm_token = get_address(&env, &DataKey::XlmToken)?;
+        let usdc_token = get_address(&env, &DataKey::UsdcToken)?;
+        let router = get_address(&env, &DataKey::Router)?;
 
         // Transfer XLM from vault to this contract
         let xlm_client = token::Client::new(&env, &xlm_token);
@@ -92,7 +153,7 @@ impl YieldStrategy {
             ).into_val(&env)
         );
 
-        let usdc_received = swap_result.get(1).unwrap();
+        let usdc_received = swap_output(&swap_result)?;
         let xlm_for_lp = amount - half_amount;
 
         // Approve router for liquidity add
@@ -101,7 +162,7 @@ impl YieldStrategy {
         usdc_client.approve(&env.current_contract_address(), &router, &usdc_received, &(env.ledger().sequence() + 1000));
 
         // Add liquidity
-        let (amount_a, amount_b, liquidity): (i128, i128, i128) = env.invoke_contract(
+        let (_amount_a, _amount_b, liquidity): (i128, i128, i128) = env.invoke_contract(
             &router,
             &symbol_short!("add_liq"),  // add_liquidity
             (
@@ -117,25 +178,48 @@ impl YieldStrategy {
         );
 
         // Update LP balance
-        let current_lp: i128 = env.storage().instance().get(&DataKey::LpBalance).unwrap_or(0);
-        env.storage().instance().set(&DataKey::LpBalance, &(current_lp + liquidity));
+        let lp_total = get_amount(&env, &DataKey::LpBalance)
+            .checked_add(liquidity)
+            .ok_or(StrategyError::Overflow)?;
+        env.storage().instance().set(&DataKey::LpBalance, &lp_total);
 
-        let total_deposited: i128 = env.storage().instance().get(&DataKey::TotalDeposited).unwrap_or(0);
-        env.storage().instance().set(&DataKey::TotalDeposited, &(total_deposited + amount));
+        let total_deposited = get_amount(&env, &DataKey::TotalDeposited)
+            .checked_add(amount)
+            .ok_or(StrategyError::Overflow)?;
+        env.storage().instance().set(&DataKey::TotalDeposited, &total_deposited);
 
-        liquidity
+        Ok(amount)
     }
 
-    /// Withdraw from Soroswap and return XLM to vault
-    pub fn withdraw(env: Env, to: Address, lp_amount: i128) -> i128 {
-        let vault: Address = env.storage().instance().get(&DataKey::Vault).unwrap();
-        if to != vault {
-            panic!("Only vault can withdraw");
+    /// Withdraw up to `amount` XLM worth of liquidity and return it to the vault
+    /// Returns the XLM amount actually sent
+    pub fn withdraw(env: Env, to: Address, amount: i128) -> Result<i128, StrategyError> {
+        require_vault(&env, &to)?;
+        if amount <= 0 {
+            return Err(StrategyError::InvalidAmount);
+        }
+
+        // Convert the requested XLM value into a share of the LP position
+        let lp_total = get_amount(&env, &DataKey::LpBalance);
+        if lp_total == 0 {
+            return Ok(0);
+        }
+        let value = position_value(&env)?;
+        if value == 0 {
+            return Err(StrategyError::ZeroValue);
+        }
+        let lp_amount = if amount >= value {
+            lp_total
+        } else {
+            mul_div(lp_total, amount, value)?
+        };
+        if lp_amount == 0 {
+            return Ok(0);
         }
 
-        let xlm_token: Address = env.storage().instance().get(&DataKey::XlmToken).unwrap();
-        let usdc_token: Address = env.storage().instance().get(&DataKey::UsdcToken).unwrap();
-        let router: Address = env.storage().instance().get(&DataKey::Router).unwrap();
+        let xlm_token = get_address(&env, &DataKey::XlmToken)?;
+        let usdc_token = get_address(&env, &DataKey::UsdcToken)?;
+        let router = get_address(&env, &DataKey::Router)?;
 
         // Remove liquidity
         let (xlm_received, usdc_received): (i128, i128) = env.invoke_contract(
@@ -153,8 +237,8 @@ impl YieldStrategy {
         );
 
         // Swap USDC back to XLM
-        let path: Vec<Address> = Vec::from_array(&env, [usdc_token, xlm_token.clone()]);
-        let usdc_client = token::Client::new(&env, &env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap());
+        let path: Vec<Address> = Vec::from_array(&env, [usdc_token.clone(), xlm_token.clone()]);
+        let usdc_client = token::Client::new(&env, &usdc_token);
         usdc_client.approve(&env.current_contract_address(), &router, &usdc_received, &(env.ledger().sequence() + 1000));
 
         let swap_result: Vec<i128> = env.invoke_contract(
@@ -169,51 +253,41 @@ impl YieldStrategy {
             ).into_val(&env)
         );
 
-        let total_xlm = xlm_received + swap_result.get(1).unwrap();
+        let total_xlm = xlm_received
+            .checked_add(swap_output(&swap_result)?)
+            .ok_or(StrategyError::Overflow)?;
 
         // Transfer XLM to vault
         let xlm_client = token::Client::new(&env, &xlm_token);
         xlm_client.transfer(&env.current_contract_address(), &to, &total_xlm);
 
-        // Update LP balance
-        let current_lp: i128 = env.storage().instance().get(&DataKey::LpBalance).unwrap_or(0);
-        env.storage().instance().set(&DataKey::LpBalance, &(current_lp - lp_amount));
+        // Update LP balance and principal
+        env.storage().instance().set(&DataKey::LpBalance, &(lp_total - lp_amount));
+        let total_deposited = get_amount(&env, &DataKey::TotalDeposited);
+        let principal_out = mul_div(total_deposited, lp_amount, lp_total)?;
+        env.storage().instance().set(&DataKey::TotalDeposited, &(total_deposited - principal_out));
 
-        total_xlm
+        Ok(total_xlm)
     }
 
     /// Get current value of LP position in XLM terms
-    pub fn get_value(env: Env) -> i128 {
-        let lp_balance: i128 = env.storage().instance().get(&DataKey::LpBalance).unwrap_or(0);
-        if lp_balance == 0 {
-            return 0;
-        }
-
-        // In production, query the pool for actual value
-        // For now, estimate with 0.3% fee accumulation simulation
-        let total_deposited: i128 = env.storage().instance().get(&DataKey::TotalDeposited).unwrap_or(0);
-
-        // Simulate ~0.05% yield per call (trading fees)
-        total_deposited * 10005 / 10000
+    pub fn balance(env: Env) -> Result<i128, StrategyError> {
+        position_value(&env)
     }
 
-    /// Harvest trading fees and return as yield
-    pub fn harvest(env: Env) -> i128 {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+    /// Harvest trading fees and return as yield (vault only). Always zero
+    /// while the position is valued at principal.
+    pub fn harvest(env: Env) -> Result<i128, StrategyError> {
+        let vault = get_address(&env, &DataKey::Vault)?;
+        vault.require_auth();
 
-        let current_value = Self::get_value(env.clone());
-        let total_deposited: i128 = env.storage().instance().get(&DataKey::TotalDeposited).unwrap_or(0);
-
-        if current_value > total_deposited {
-            current_value - total_deposited
-        } else {
-            0
-        }
+        let current_value = position_value(&env)?;
+        let total_deposited = get_amount(&env, &DataKey::TotalDeposited);
+        Ok((current_value - total_deposited).max(0))
     }
 
     pub fn get_lp_balance(env: Env) -> i128 {
-        env.storage().instance().get(&DataKey::LpBalance).unwrap_or(0)
+        get_amount(&env, &DataKey::LpBalance)
     }
 }
 
@@ -14,8 +14,7 @@ import {
   CONFIG,
   getXlmBalance,
   getSxlmBalance,
-  getExchangeRate,
-  getTotalAssets,
+  getVaultState,
   buildDepositTx,
   buildWithdrawTx,
   submitSignedTx,
@@ -50,9 +49,13 @@ export default function Home() {
 
   const loadProtocolStats = useCallback(async () => {
     try {
-      const [rate, assets] = await Promise.all([getExchangeRate(), getTotalAssets()]);
+      const state = await getVaultState();
+      if (!state) {
+        return;
+      }
+      const rate = state.exchangeRate;
       setExchangeRate(rate);
-      setTvl(assets);
+      setTvl(state.totalAssets);
 
       // Calculate APY based on rate growth
       // Rate started at 1.0, current rate shows total growth
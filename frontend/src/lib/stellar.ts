@@ -90,6 +90,39 @@ export async function getTotalAssets(): Promise<number> {
   return 0;
 }
 
+export interface VaultState {
+  totalAssets: number;
+  idleAssets: number;
+  totalSupply: number;
+  exchangeRate: number;
+  pendingWithdrawals: number;
+  withdrawCapacity: number;
+  totalCap: number;
+  paused: boolean;
+  shutdown: boolean;
+}
+
+// One simulation for everything the dashboard shows
+export async function getVaultState(): Promise<VaultState | null> {
+  const result = await callContractView(CONFIG.vault, 'vault_state', []);
+  if (!result) {
+    return null;
+  }
+  const state = scValToNative(result);
+  const pause = state.pause;
+  return {
+    totalAssets: stroopsToXlm(state.total_assets),
+    idleAssets: stroopsToXlm(state.idle_assets),
+    totalSupply: stroopsToXlm(state.total_supply),
+    exchangeRate: Number(state.exchange_rate) / Math.pow(10, CONFIG.decimals),
+    pendingWithdrawals: stroopsToXlm(state.pending_withdrawals),
+    withdrawCapacity: stroopsToXlm(state.withdraw_capacity),
+    totalCap: stroopsToXlm(state.total_cap),
+    paused: pause.deposits || pause.withdrawals || pause.withdraw_only || pause.shutdown,
+    shutdown: pause.shutdown,
+  };
+}
+
 export async function getSxlmBalance(address: string): Promise<number> {
   const result = await callContractView(CONFIG.sxlmToken, 'balance', [
     nativeToScVal(address, { type: 'address' })
@@ -3,6 +3,9 @@
 VAULT="CBT3MV2YU2FBQV7QNSAKGIWYRTQTKLCXBIZBKR2T3TRDWJKOCXQ53EFV"
 SXLM="CDTWBLUQAEXAQ6JWYZUS7ZTBFWCVBGZA5XYTTJ7C25QJX7PBTZNL6BDF"
 READ_ACCOUNT="GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF"
+# Identity holding the vault's Keeper role (not the owner/deployer key).
+# Create and grant it once, see "Keeper identity" in the README.
+KEEPER="${KEEPER:-keeper}"
 
 show_status() {
     echo ""
@@ -32,10 +35,10 @@ add_yield() {
 
     stellar contract invoke \
         --id $VAULT \
-        --source-account deployer \
+        --source-account $KEEPER \
         --network testnet \
         --send=yes \
-        -- add_yield --amount $STROOPS 2>&1 | grep -E "(Success|yield)"
+        -- add_yield --caller $KEEPER --amount $STROOPS 2>&1 | grep -E "(Success|yield)"
 
     show_status
 }
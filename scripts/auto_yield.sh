@@ -4,6 +4,9 @@
 # Run this with cron: 0 0 * * * /path/to/auto_yield.sh
 
 VAULT_ID="CBT3MV2YU2FBQV7QNSAKGIWYRTQTKLCXBIZBKR2T3TRDWJKOCXQ53EFV"
+# Identity holding the vault's Keeper role (not the owner/deployer key).
+# Create and grant it once, see "Keeper identity" in the README.
+KEEPER="${KEEPER:-keeper}"
 
 # Get current total assets
 TOTAL=$(stellar contract invoke --id $VAULT_ID --source-account GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF --network testnet -- get_total_assets 2>&1 | grep -o '"[0-9]*"' | tr -d '"')
@@ -15,8 +18,8 @@ if [ "$YIELD" -gt 0 ]; then
     echo "Adding yield: $YIELD stroops"
     stellar contract invoke \
         --id $VAULT_ID \
-        --source-account deployer \
+        --source-account $KEEPER \
         --network testnet \
         --send=yes \
-        -- add_yield --amount $YIELD
+        -- add_yield --caller $KEEPER --amount $YIELD
 fi
@@ -8,6 +8,7 @@ SXLM_TOKEN="CDTWBLUQAEXAQ6JWYZUS7ZTBFWCVBGZA5XYTTJ7C25QJX7PBTZNL6BDF"
 XLM_SAC="CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC"
 NETWORK="testnet"
 SOURCE="deployer"
+KEEPER="${KEEPER:-keeper}" # Holds the Keeper role, see "Keeper identity" in the README
 
 echo "=== sXLM Protocol v2 CLI ==="
 echo ""
@@ -16,23 +17,7 @@ case "$1" in
   "stats")
     echo "Fetching protocol stats..."
     echo ""
-    echo "Exchange Rate (scaled by 10^7):"
-    stellar contract invoke --id $VAULT --source $SOURCE --network $NETWORK -- get_exchange_rate
-    echo ""
-    echo "Total Assets (stroops):"
-    stellar contract invoke --id $VAULT --source $SOURCE --network $NETWORK -- get_total_assets
-    echo ""
-    echo "Total Deposits (stroops):"
-    stellar contract invoke --id $VAULT --source $SOURCE --network $NETWORK -- get_total_deposits
-    echo ""
-    echo "Yield Accrued (stroops):"
-    stellar contract invoke --id $VAULT --source $SOURCE --network $NETWORK -- get_yield_accrued
-    echo ""
-    echo "Total sXLM Supply (stroops):"
-    stellar contract invoke --id $SXLM_TOKEN --source $SOURCE --network $NETWORK -- total_supply
-    echo ""
-    echo "Is Paused:"
-    stellar contract invoke --id $VAULT --source $SOURCE --network $NETWORK -- is_paused
+    stellar contract invoke --id $VAULT --source $SOURCE --network $NETWORK -- vault_state
     ;;
 
   "deposit")
@@ -141,31 +126,60 @@ case "$1" in
       echo "Example: ./interact.sh add-yield 100000000  (10 XLM yield)"
       exit 1
     fi
-    echo "Adding $2 stroops yield (admin only)..."
+    echo "Adding $2 stroops yield (keeper only)..."
     stellar contract invoke \
       --id $VAULT \
-      --source $SOURCE \
+      --source $KEEPER \
       --network $NETWORK \
       -- add_yield \
+      --caller $KEEPER \
       --amount $2
     ;;
 
   "pause")
-    echo "Pausing vault (admin only)..."
+    echo "Pausing vault (guardian only)..."
     stellar contract invoke \
       --id $VAULT \
       --source $SOURCE \
       --network $NETWORK \
-      -- pause
+      -- pause \
+      --caller $SOURCE
     ;;
 
   "unpause")
-    echo "Unpausing vault (admin only)..."
+    echo "Unpausing vault (guardian only)..."
+    stellar contract invoke \
+      --id $VAULT \
+      --source $SOURCE \
+      --network $NETWORK \
+      -- unpause \
+      --caller $SOURCE
+    ;;
+
+  "emergency-shutdown")
+    echo "Permanently shutting down the vault (guardian only)..."
+    stellar contract invoke \
+      --id $VAULT \
+      --source $SOURCE \
+      --network $NETWORK \
+      -- emergency_shutdown \
+      --caller $SOURCE
+    ;;
+
+  "emergency-redeem")
+    if [ -z "$2" ]; then
+      echo "Usage: ./interact.sh emergency-redeem <sxlm_amount_in_stroops>"
+      exit 1
+    fi
+    USER=$(stellar keys address $SOURCE)
+    echo "Redeeming $2 stroops sXLM at the shutdown rate..."
     stellar contract invoke \
       --id $VAULT \
       --source $SOURCE \
       --network $NETWORK \
-      -- unpause
+      -- emergency_redeem \
+      --user $USER \
+      --shares $2
     ;;
 
   "set-max-deposit")
@@ -173,13 +187,40 @@ case "$1" in
       echo "Usage: ./interact.sh set-max-deposit <max_amount_in_stroops>"
       exit 1
     fi
-    echo "Setting max deposit to $2 stroops (admin only)..."
+    echo "Scheduling max deposit of $2 stroops behind the timelock (strategist only)..."
+    stellar contract invoke \
+      --id $VAULT \
+      --source $SOURCE \
+      --network $NETWORK \
+      -- schedule \
+      --caller $SOURCE \
+      --action "{\"SetMaxDeposit\":\"$2\"}"
+    ;;
+
+  "execute")
+    if [ -z "$2" ]; then
+      echo "Usage: ./interact.sh execute <operation_id>"
+      exit 1
+    fi
+    echo "Executing timelocked operation $2..."
+    stellar contract invoke \
+      --id $VAULT \
+      --source $SOURCE \
+      --network $NETWORK \
+      -- execute \
+      --id $2
+    ;;
+
+  "grant-keeper")
+    KEEPER_ADDRESS=$(stellar keys address $KEEPER)
+    echo "Granting the Keeper role to $KEEPER ($KEEPER_ADDRESS) (owner only)..."
     stellar contract invoke \
       --id $VAULT \
       --source $SOURCE \
       --network $NETWORK \
-      -- set_max_deposit \
-      --max_deposit $2
+      -- grant_role \
+      --role Keeper \
+      --account $KEEPER_ADDRESS
     ;;
 
   "info")
@@ -206,10 +247,14 @@ case "$1" in
     echo "  withdraw-xlm <xlm> - Withdraw sXLM (in sXLM)"
     echo "  preview-deposit    - Preview deposit output"
     echo "  preview-withdraw   - Preview withdrawal output"
-    echo "  add-yield <amount> - Add yield (admin only)"
-    echo "  pause              - Pause vault (admin only)"
-    echo "  unpause            - Unpause vault (admin only)"
-    echo "  set-max-deposit    - Set max deposit (admin only)"
+    echo "  add-yield <amount> - Add yield, signed by \$KEEPER (keeper only)"
+    echo "  grant-keeper       - Grant the Keeper role to \$KEEPER (owner only)"
+    echo "  pause              - Pause vault (guardian only)"
+    echo "  unpause            - Unpause vault (guardian only)"
+    echo "  emergency-shutdown - Shut the vault down for good (guardian only)"
+    echo "  emergency-redeem   - Redeem sXLM after shutdown (in stroops)"
+    echo "  set-max-deposit    - Schedule max deposit change (strategist only)"
+    echo "  execute <id>       - Execute a timelocked operation after its delay"
     echo ""
     echo "Note: 1 XLM = 10,000,000 stroops (7 decimals)"
     ;;
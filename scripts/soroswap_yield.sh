@@ -7,14 +7,18 @@ SOROSWAP_ROUTER="CCJUD55AG6W5HAI5LRVNKAE5WDP5XGZBUDS5WNTIVDU7O264UZZE7BRD"
 SOROSWAP_FACTORY="CDP3HMUH6SMS3S7NPGNDJLULCOXXEPSHY4JKUKMBNQMATHDHWXRRJTBY"
 VAULT="CBT3MV2YU2FBQV7QNSAKGIWYRTQTKLCXBIZBKR2T3TRDWJKOCXQ53EFV"
 XLM_TOKEN="CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC"
+# Identity holding the vault's Keeper role (not the owner/deployer key).
+# Create and grant it once, see "Keeper identity" in the README.
+KEEPER="${KEEPER:-keeper}"
 
 echo "=== Soroswap Yield Strategy ==="
 echo ""
 
 # Check current vault state
 echo "1. Current Vault State:"
-TOTAL_ASSETS=$(stellar contract invoke --id $VAULT --source-account GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF --network testnet -- get_total_assets 2>&1 | grep -o '"[0-9]*"' | tr -d '"')
-EXCHANGE_RATE=$(stellar contract invoke --id $VAULT --source-account GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF --network testnet -- get_exchange_rate 2>&1 | grep -o '"[0-9]*"' | tr -d '"')
+STATE=$(stellar contract invoke --id $VAULT --source-account GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF --network testnet -- vault_state 2>/dev/null)
+TOTAL_ASSETS=$(echo "$STATE" | jq -r '.total_assets')
+EXCHANGE_RATE=$(echo "$STATE" | jq -r '.exchange_rate')
 
 echo "   Total Assets: $((TOTAL_ASSETS / 10000000)) XLM"
 echo "   Exchange Rate: $(echo "scale=4; $EXCHANGE_RATE / 10000000" | bc)"
@@ -33,17 +37,18 @@ if [ "$YIELD" -gt 0 ]; then
     echo "3. Adding yield to vault..."
     stellar contract invoke \
         --id $VAULT \
-        --source-account deployer \
+        --source-account $KEEPER \
         --network testnet \
         --send=yes \
-        -- add_yield --amount $YIELD 2>&1 | grep -E "(Success|Event)"
+        -- add_yield --caller $KEEPER --amount $YIELD 2>&1 | grep -E "(Success|Event)"
     echo ""
 fi
 
 # Check new state
 echo "4. Updated Vault State:"
-NEW_TOTAL=$(stellar contract invoke --id $VAULT --source-account GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF --network testnet -- get_total_assets 2>&1 | grep -o '"[0-9]*"' | tr -d '"')
-NEW_RATE=$(stellar contract invoke --id $VAULT --source-account GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF --network testnet -- get_exchange_rate 2>&1 | grep -o '"[0-9]*"' | tr -d '"')
+NEW_STATE=$(stellar contract invoke --id $VAULT --source-account GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF --network testnet -- vault_state 2>/dev/null)
+NEW_TOTAL=$(echo "$NEW_STATE" | jq -r '.total_assets')
+NEW_RATE=$(echo "$NEW_STATE" | jq -r '.exchange_rate')
 
 echo "   Total Assets: $((NEW_TOTAL / 10000000)) XLM"
 echo "   Exchange Rate: $(echo "scale=4; $NEW_RATE / 10000000" | bc)"
@@ -5,6 +5,9 @@
 
 VAULT="CBT3MV2YU2FBQV7QNSAKGIWYRTQTKLCXBIZBKR2T3TRDWJKOCXQ53EFV"
 READ_ACCOUNT="GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF"
+# Identity holding the vault's Keeper role (not the owner/deployer key).
+# Create and grant it once, see "Keeper identity" in the README.
+KEEPER="${KEEPER:-keeper}"
 
 # Yield rate: 0.1% per cycle (simulating APY)
 YIELD_RATE=1  # 0.1% = 1/1000
@@ -13,6 +16,7 @@ INTERVAL=60  # seconds between yield additions
 
 echo "🤖 sXLM Yield Bot Started"
 echo "   Vault: $VAULT"
+echo "   Keeper: $KEEPER"
 echo "   Interval: ${INTERVAL}s"
 echo "   Yield Rate: 0.1% per cycle"
 echo ""
@@ -40,10 +44,10 @@ while true; do
         # Add yield to vault
         RESULT=$(stellar contract invoke \
             --id $VAULT \
-            --source-account deployer \
+            --source-account $KEEPER \
             --network testnet \
             --send=yes \
-            -- add_yield --amount $YIELD 2>&1)
+            -- add_yield --caller $KEEPER --amount $YIELD 2>&1)
 
         # Get new exchange rate
         RATE=$(stellar contract invoke \